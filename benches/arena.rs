@@ -0,0 +1,45 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kjson::{Arena, ArenaValue, Value};
+
+// 一批小消息，每条都是一个独立的文档
+fn messages(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            format!(
+                r#"{{"id":{i},"type":"order","user":"user-{}","items":[{{"sku":"s-{i}","qty":{}}},{{"sku":"t-{i}","qty":1}}],"total":{},"paid":{}}}"#,
+                i % 97,
+                i % 5 + 1,
+                i as f64 * 1.25,
+                i % 3 == 0
+            )
+        })
+        .collect()
+}
+
+fn bench_arena(c: &mut Criterion) {
+    let messages = messages(10_000);
+    let bytes: usize = messages.iter().map(String::len).sum();
+
+    let mut group = c.benchmark_group("messages");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("value", |b| {
+        b.iter(|| {
+            for message in &messages {
+                black_box(Value::parse(black_box(message)).unwrap());
+            }
+        })
+    });
+    group.bench_function("arena", |b| {
+        let mut arena = Arena::new();
+        b.iter(|| {
+            for message in &messages {
+                arena.reset();
+                black_box(ArenaValue::parse(black_box(message), &arena).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_arena);
+criterion_main!(benches);
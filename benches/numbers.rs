@@ -0,0 +1,64 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kjson::Value;
+
+// 构造类似GeoJSON的文档：大量经纬度坐标
+fn geojson(features: usize) -> String {
+    let mut seed: u64 = 42;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as f64 / (1u64 << 31) as f64
+    };
+    let mut s = String::from(r#"{"type":"FeatureCollection","features":["#);
+    for i in 0..features {
+        if i > 0 {
+            s.push(',');
+        }
+        s.push_str(r#"{"type":"Feature","properties":{"id":"#);
+        s.push_str(&i.to_string());
+        s.push_str(r#"},"geometry":{"type":"Polygon","coordinates":[["#);
+        for j in 0..32 {
+            if j > 0 {
+                s.push(',');
+            }
+            let lon = -180.0 + 360.0 * next();
+            let lat = -90.0 + 180.0 * next();
+            s.push_str(&format!("[{lon:.6},{lat:.6}]"));
+        }
+        s.push_str("]]}}");
+    }
+    s.push_str("]}");
+    s
+}
+
+// 大整数和长小数，会走回退路径
+fn mixed_numbers(count: usize) -> String {
+    let mut s = String::from("[");
+    for i in 0..count {
+        if i > 0 {
+            s.push(',');
+        }
+        match i % 4 {
+            0 => s.push_str(&(i as u64 * 2654435761).to_string()),
+            1 => s.push_str(&format!("{}", u64::MAX - i as u64)),
+            2 => s.push_str(&format!("{:e}", i as f64 * 1.234_567_890_123e-200)),
+            _ => s.push_str(&format!("{}", 1.0 / (i as f64 + 3.0))),
+        }
+    }
+    s.push(']');
+    s
+}
+
+fn bench_numbers(c: &mut Criterion) {
+    let geo = geojson(500);
+    let mixed = mixed_numbers(20_000);
+
+    let mut group = c.benchmark_group("numbers");
+    group.throughput(Throughput::Bytes(geo.len() as u64));
+    group.bench_function("geojson", |b| b.iter(|| Value::parse(black_box(&geo)).unwrap()));
+    group.throughput(Throughput::Bytes(mixed.len() as u64));
+    group.bench_function("mixed", |b| b.iter(|| Value::parse(black_box(&mixed)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_numbers);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kjson::Value;
+
+// 混合的文档：缩进的对象、较长的字符串和数字数组，空白和字符串内容占大部分字节
+fn document(records: usize) -> String {
+    let mut s = String::from("[\n");
+    for i in 0..records {
+        if i > 0 {
+            s.push_str(",\n");
+        }
+        s.push_str(&format!(
+            r#"    {{
+        "id": {i},
+        "name": "record number {i} with a moderately long descriptive name",
+        "tags": ["alpha", "beta", "gamma", "delta"],
+        "scores": [{}, {}, {}],
+        "active": {},
+        "note": "line one\nline two \"quoted\" and some more text to skip over"
+    }}"#,
+            i * 3,
+            i as f64 / 7.0,
+            -(i as i64),
+            i % 2 == 0
+        ));
+    }
+    s.push_str("\n]");
+    s
+}
+
+fn bench_simd(c: &mut Criterion) {
+    let doc = document(5_000);
+
+    let mut group = c.benchmark_group("two_stage");
+    group.throughput(Throughput::Bytes(doc.len() as u64));
+    group.bench_function("parse", |b| b.iter(|| Value::parse(black_box(&doc)).unwrap()));
+    group.bench_function("parse_simd", |b| b.iter(|| Value::parse_simd(black_box(&doc)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_simd);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kjson::{BorrowedValue, Value};
+
+// 构造以字符串为主的文档：类似日志的记录，少量字符串含转义或非ASCII字符
+fn log_records(count: usize) -> String {
+    let words = [
+        "request", "handled", "upstream", "timeout", "user", "session", "cache", "miss",
+    ];
+    let mut s = String::from("[");
+    for i in 0..count {
+        if i > 0 {
+            s.push(',');
+        }
+        let message: Vec<&str> = (0..12).map(|j| words[(i * 7 + j * 3) % words.len()]).collect();
+        s.push_str(r#"{"level":"info","logger":"kjson.server.handler","message":""#);
+        s.push_str(&message.join(" "));
+        if i % 10 == 0 {
+            s.push_str(r#" \"quoted\"\tand\\escaped"#);
+        }
+        if i % 10 == 5 {
+            s.push_str(" 请求已处理 ✓");
+        }
+        s.push_str(r#"","host":"node-"#);
+        s.push_str(&(i % 16).to_string());
+        s.push_str(r#".example.internal","path":"/api/v1/items/search"}"#);
+    }
+    s.push(']');
+    s
+}
+
+fn bench_strings(c: &mut Criterion) {
+    let logs = log_records(5_000);
+
+    let mut group = c.benchmark_group("strings");
+    group.throughput(Throughput::Bytes(logs.len() as u64));
+    group.bench_function("value", |b| b.iter(|| Value::parse(black_box(&logs)).unwrap()));
+    group.bench_function("borrowed", |b| {
+        b.iter(|| BorrowedValue::parse(black_box(&logs)).unwrap())
+    });
+    // 从Read解析时无法借用输入，所有字符串都要复制
+    group.bench_function("reader", |b| {
+        b.iter(|| Value::from_reader(black_box(logs.as_bytes())).unwrap())
+    });
+    // 只遍历事件，不构建值
+    group.bench_function("events", |b| {
+        b.iter(|| {
+            let mut parser = kjson::Parser::new(black_box(logs.as_bytes()));
+            let mut n = 0;
+            while let Some(event) = parser.next_event().unwrap() {
+                if let kjson::Event::String(s) = event {
+                    n += s.len();
+                }
+            }
+            n
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_strings);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kjson::{Tape, Value};
+
+// 一组记录，每条记录有若干标量字段和一个小的嵌套对象
+fn records(count: usize) -> String {
+    let mut s = String::from(r#"{"records":["#);
+    for i in 0..count {
+        if i > 0 {
+            s.push(',');
+        }
+        s.push_str(&format!(
+            r#"{{"id":{i},"name":"item-{i}","price":{},"tags":["a","b","c"],"meta":{{"owner":"team-{}","active":{}}}}}"#,
+            i as f64 * 0.25,
+            i % 7,
+            i % 2 == 0
+        ));
+    }
+    s.push_str("]}");
+    s
+}
+
+fn bench_tape(c: &mut Criterion) {
+    let json = records(10_000);
+    let value = Value::parse(&json).unwrap();
+    let tape = Tape::parse(&json).unwrap();
+
+    let mut group = c.benchmark_group("tape");
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("build_value", |b| b.iter(|| Value::parse(black_box(&json)).unwrap()));
+    group.bench_function("build_tape", |b| b.iter(|| Tape::parse(black_box(&json)).unwrap()));
+    group.finish();
+
+    // 遍历所有记录并读取其中几个字段
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("value", |b| {
+        b.iter(|| {
+            let Value::Array(records) = &black_box(&value)["records"] else {
+                unreachable!()
+            };
+            records
+                .iter()
+                .filter(|r| matches!(r["meta"]["active"], Value::Bool(true)))
+                .filter_map(|r| match &r["price"] {
+                    Value::Number(n) => n.as_f64(),
+                    _ => None,
+                })
+                .sum::<f64>()
+        })
+    });
+    group.bench_function("tape", |b| {
+        b.iter(|| {
+            black_box(&tape)["records"]
+                .iter()
+                .filter(|r| r["meta"]["active"].as_bool() == Some(true))
+                .filter_map(|r| r["price"].as_f64())
+                .sum::<f64>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tape);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+#![no_main]
+
+// 任意输入都只能得到Ok或ParseError，不能panic:
+// cargo +nightly fuzz run parse
+use kjson::{IncrementalParser, Parser, Value};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let result = Value::parse_slice(data);
+
+    // 从Read解析与从切片解析的结果完全一致
+    assert_eq!(Value::from_reader(data), result);
+
+    let mut parser = Parser::new(data);
+    while let Ok(Some(_)) = parser.next_event() {}
+
+    // 增量解析在任意位置切分输入都能得到相同的值
+    let split = data.first().map_or(0, |&b| b as usize % (data.len() + 1));
+    let mut incremental = IncrementalParser::new();
+    let values = incremental.feed(&data[..split]).and_then(|mut values| {
+        values.extend(incremental.feed(&data[split..])?);
+        values.extend(incremental.finish()?);
+        Ok(values)
+    });
+
+    match (result, std::str::from_utf8(data)) {
+        (Ok(value), _) => {
+            assert_eq!(values.ok(), Some(vec![value.clone()]));
+            let _ = value.to_string();
+        }
+        (Err(e), Ok(source)) => {
+            let _ = e.diagnostic(source).to_string();
+        }
+        (Err(_), Err(_)) => {}
+    }
+});
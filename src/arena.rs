@@ -0,0 +1,474 @@
+use crate::error::{ParseError, ParseErrorKind, WriteError};
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParseOptions, WriteOptions};
+use crate::parser::{Event, Parser};
+use crate::value::Value;
+use bumpalo::Bump;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Index;
+use std::ptr::NonNull;
+
+// ArenaValue::Object使用的对象类型：按文档顺序排列的成员，重复的键已在解析时合并
+pub type ArenaMap<'b> = [(&'b str, ArenaValue<'b>)];
+
+// 分配在Arena中的Value：字符串、数组和对象都放在Arena里，整个文档随Arena一起释放，
+// 不会逐个释放节点。只包含引用，因此可以随意复制
+#[derive(Debug, Clone, Copy)]
+pub enum ArenaValue<'b> {
+    Null,
+    Bool(bool),
+    Number(&'b Number),
+    String(&'b str),
+    Array(&'b [ArenaValue<'b>]),
+    Object(&'b ArenaMap<'b>),
+}
+
+// 为ArenaValue提供内存的竞技场；reset之后可以用于解析下一个文档，已申请的内存会被复用
+#[derive(Debug, Default)]
+pub struct Arena {
+    bump: Bump,
+    // 需要析构的数字（启用arbitrary_precision时Number含有String），在reset或释放时析构
+    numbers: RefCell<Vec<NonNull<Number>>>,
+}
+
+// SAFETY: numbers中的指针都指向bump中由Arena独占的数字，Bump和Number都是Send
+unsafe impl Send for Arena {}
+
+impl Arena {
+    pub fn new() -> Self {
+        Arena::default()
+    }
+
+    pub fn with_capacity(bytes: usize) -> Self {
+        Arena {
+            bump: Bump::with_capacity(bytes),
+            numbers: RefCell::new(Vec::new()),
+        }
+    }
+
+    // 释放所有文档，保留已申请的内存供之后的文档使用；需要&mut self，因此不会有ArenaValue仍在借用
+    pub fn reset(&mut self) {
+        self.drop_numbers();
+        self.bump.reset();
+    }
+
+    // 已从系统申请的字节数
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    fn alloc_number(&self, n: Number) -> &Number {
+        let n = self.bump.alloc(n);
+        if std::mem::needs_drop::<Number>() {
+            self.numbers.borrow_mut().push(NonNull::from(&mut *n));
+        }
+        n
+    }
+
+    fn drop_numbers(&mut self) {
+        for n in self.numbers.get_mut().drain(..) {
+            // SAFETY: 每个数字只登记一次；&mut self保证已没有ArenaValue借用它们
+            unsafe { n.as_ptr().drop_in_place() }
+        }
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        self.drop_numbers();
+    }
+}
+
+// 对象的成员超过这个数时，为查找重复的键建立哈希索引
+const INDEXED_OBJECT_LEN: usize = 16;
+
+// 构建时尚未完成的容器，元素和成员暂存在共用的栈中，结束时一次复制到Arena
+enum Frame<'b> {
+    Array(usize),
+    Object {
+        start: usize,
+        key: &'b str,
+        index: HashMap<&'b str, usize>,
+        // CollectAll策略下值已被收集为数组的成员
+        collected: Vec<(usize, Vec<ArenaValue<'b>>)>,
+    },
+}
+
+impl<'b> ArenaValue<'b> {
+    pub fn parse(json: &str, arena: &'b Arena) -> Result<ArenaValue<'b>, ParseError> {
+        ArenaValue::parse_slice(json.as_bytes(), arena)
+    }
+
+    pub fn parse_slice(json: &[u8], arena: &'b Arena) -> Result<ArenaValue<'b>, ParseError> {
+        ArenaValue::parse_document(Parser::new(json), arena)
+    }
+
+    pub fn parse_with_options(
+        json: &str,
+        options: ParseOptions,
+        arena: &'b Arena,
+    ) -> Result<ArenaValue<'b>, ParseError> {
+        ArenaValue::parse_document(Parser::new(json.as_bytes()).with_options(options), arena)
+    }
+
+    fn parse_document(mut parser: Parser, arena: &'b Arena) -> Result<ArenaValue<'b>, ParseError> {
+        let v = ArenaValue::from_parser(&mut parser, arena)?;
+        match parser.next_event()? {
+            None => Ok(v),
+            Some(_) => Err(parser.error(ParseErrorKind::RootNotSingular)),
+        }
+    }
+
+    // 从事件流中读取下一个完整的值，字符串都复制到Arena中
+    pub fn from_parser(parser: &mut Parser, arena: &'b Arena) -> Result<ArenaValue<'b>, ParseError> {
+        let policy = parser.options().duplicate_keys;
+        let mut frames: Vec<Frame<'b>> = Vec::new();
+        let mut elements: Vec<ArenaValue<'b>> = Vec::new();
+        let mut members: Vec<(&'b str, ArenaValue<'b>)> = Vec::new();
+        loop {
+            let value = match parser.next_event()? {
+                Some(Event::StartArray) => {
+                    frames.push(Frame::Array(elements.len()));
+                    continue;
+                }
+                Some(Event::StartObject) => {
+                    frames.push(Frame::Object {
+                        start: members.len(),
+                        key: "",
+                        index: HashMap::new(),
+                        collected: Vec::new(),
+                    });
+                    continue;
+                }
+                Some(Event::Key(k)) if !frames.is_empty() => {
+                    let k = arena.bump.alloc_str(k);
+                    if let Some(Frame::Object { start, key, index, .. }) = frames.last_mut() {
+                        if policy == DuplicateKeys::Error && find_member(&members[*start..], index, k).is_some() {
+                            return Err(parser.error_at(parser.key_offset(), ParseErrorKind::DuplicateKey));
+                        }
+                        *key = k;
+                    }
+                    continue;
+                }
+                Some(Event::EndArray) if !frames.is_empty() => {
+                    let Some(Frame::Array(start)) = frames.pop() else {
+                        unreachable!("the parser checks that brackets match");
+                    };
+                    let arr = arena.bump.alloc_slice_copy(&elements[start..]);
+                    elements.truncate(start);
+                    ArenaValue::Array(arr)
+                }
+                Some(Event::EndObject) if !frames.is_empty() => {
+                    let Some(Frame::Object { start, collected, .. }) = frames.pop() else {
+                        unreachable!("the parser checks that brackets match");
+                    };
+                    for (i, values) in collected {
+                        members[start + i].1 = ArenaValue::Array(arena.bump.alloc_slice_copy(&values));
+                    }
+                    let object = arena.bump.alloc_slice_copy(&members[start..]);
+                    members.truncate(start);
+                    ArenaValue::Object(object)
+                }
+                Some(Event::Null) => ArenaValue::Null,
+                Some(Event::Bool(b)) => ArenaValue::Bool(b),
+                Some(Event::Number(n)) => ArenaValue::Number(arena.alloc_number(n)),
+                Some(Event::String(s)) => ArenaValue::String(arena.bump.alloc_str(s)),
+                Some(Event::Key(_) | Event::EndArray | Event::EndObject) | None => {
+                    return Err(parser.error(ParseErrorKind::ExpectValue));
+                }
+            };
+            match frames.last_mut() {
+                None => return Ok(value),
+                Some(Frame::Array(_)) => elements.push(value),
+                Some(Frame::Object {
+                    start,
+                    key,
+                    index,
+                    collected,
+                }) => {
+                    let object = &mut members[*start..];
+                    match (policy, find_member(object, index, key)) {
+                        (DuplicateKeys::FirstWins, Some(_)) => {}
+                        (DuplicateKeys::CollectAll, Some(i)) => match collected.iter_mut().find(|(j, _)| *j == i) {
+                            Some((_, values)) => values.push(value),
+                            None => collected.push((i, vec![object[i].1, value])),
+                        },
+                        (_, Some(i)) => object[i].1 = value,
+                        (_, None) => {
+                            members.push((*key, value));
+                            let len = members.len() - *start;
+                            if len == INDEXED_OBJECT_LEN {
+                                index.extend(members[*start..].iter().enumerate().map(|(i, (k, _))| (*k, i)));
+                            } else if len > INDEXED_OBJECT_LEN {
+                                index.insert(*key, len - 1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 在尚未完成的对象中查找键；成员较多时使用哈希索引
+fn find_member(members: &ArenaMap<'_>, index: &HashMap<&str, usize>, key: &str) -> Option<usize> {
+    if index.is_empty() {
+        members.iter().position(|(k, _)| *k == key)
+    } else {
+        index.get(key).copied()
+    }
+}
+
+impl<'b> ArenaValue<'b> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, ArenaValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ArenaValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&'b Number> {
+        match self {
+            ArenaValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'b str> {
+        match self {
+            ArenaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&'b [ArenaValue<'b>]> {
+        match self {
+            ArenaValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&'b ArenaMap<'b>> {
+        match self {
+            ArenaValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    // 按键查找对象的成员，逐个比较键
+    pub fn get(&self, key: &str) -> Option<&'b ArenaValue<'b>> {
+        self.as_object()?.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    // 复制为不再依赖Arena的Value
+    pub fn to_owned(&self) -> Value {
+        Value::from_events(self.events(), DuplicateKeys::LastWins)
+    }
+
+    pub fn to_string_with_options(&self, options: WriteOptions) -> Result<String, WriteError> {
+        Value::stringify_events(self.events(), &options)
+    }
+
+    // 按文档顺序产生事件，用显式的栈代替递归
+    fn events(&self) -> impl Iterator<Item = Event<'b>> {
+        let mut pending = Some(*self);
+        let mut stack: Vec<Members<'b>> = Vec::new();
+        std::iter::from_fn(move || {
+            if pending.is_none() {
+                match stack.last_mut()? {
+                    Members::Array(members) => match members.next() {
+                        Some(value) => pending = Some(*value),
+                        None => {
+                            stack.pop();
+                            return Some(Event::EndArray);
+                        }
+                    },
+                    Members::Object(members) => {
+                        let event = match members.next() {
+                            Some((key, value)) => {
+                                pending = Some(*value);
+                                Event::Key(key)
+                            }
+                            None => {
+                                stack.pop();
+                                Event::EndObject
+                            }
+                        };
+                        return Some(event);
+                    }
+                }
+            }
+            let event = match pending.take()? {
+                ArenaValue::Null => Event::Null,
+                ArenaValue::Bool(b) => Event::Bool(b),
+                ArenaValue::Number(n) => Event::Number(n.clone()),
+                ArenaValue::String(s) => Event::String(s),
+                ArenaValue::Array(arr) => {
+                    stack.push(Members::Array(arr.iter()));
+                    Event::StartArray
+                }
+                ArenaValue::Object(object) => {
+                    stack.push(Members::Object(object.iter()));
+                    Event::StartObject
+                }
+            };
+            Some(event)
+        })
+    }
+}
+
+// 容器中尚未遍历的成员
+enum Members<'b> {
+    Array(std::slice::Iter<'b, ArenaValue<'b>>),
+    Object(std::slice::Iter<'b, (&'b str, ArenaValue<'b>)>),
+}
+
+impl<'b> Index<&str> for ArenaValue<'b> {
+    type Output = ArenaValue<'b>;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        if let ArenaValue::Object(_) = self {
+            self.get(key).expect("key not found in object")
+        } else {
+            panic!("Only ArenaValue::Object() can be accessed by key")
+        }
+    }
+}
+
+impl<'b> Index<usize> for ArenaValue<'b> {
+    type Output = ArenaValue<'b>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if let ArenaValue::Array(arr) = self {
+            &arr[index]
+        } else {
+            panic!("Only ArenaValue::Array() can be accessed by index")
+        }
+    }
+}
+
+impl std::fmt::Display for ArenaValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_string_with_options(WriteOptions::default()) {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+// 与Value相同，对象的比较与成员顺序无关；不使用递归
+impl PartialEq for ArenaValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(*self, *other)];
+        while let Some((a, b)) = stack.pop() {
+            match (a, b) {
+                (ArenaValue::Null, ArenaValue::Null) => {}
+                (ArenaValue::Bool(a), ArenaValue::Bool(b)) if a == b => {}
+                (ArenaValue::Number(a), ArenaValue::Number(b)) if a == b => {}
+                (ArenaValue::String(a), ArenaValue::String(b)) if a == b => {}
+                (ArenaValue::Array(a), ArenaValue::Array(b)) if a.len() == b.len() => {
+                    stack.extend(a.iter().copied().zip(b.iter().copied()))
+                }
+                (ArenaValue::Object(a), ArenaValue::Object(b)) if a.len() == b.len() => {
+                    for (key, value) in a {
+                        match b.iter().find(|(k, _)| k == key) {
+                            Some((_, other)) => stack.push((*value, *other)),
+                            None => return false,
+                        }
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Eq for ArenaValue<'_> {}
+
+impl From<ArenaValue<'_>> for Value {
+    fn from(value: ArenaValue<'_>) -> Self {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_accessors() {
+        let arena = Arena::new();
+        let json = r#"{"name": "kjson", "list": [1, 2.5, true, null, "x\n"], "nested": {"a": {"c": []}}}"#;
+        let v = ArenaValue::parse(json, &arena).unwrap();
+        assert_eq!(v["name"].as_str(), Some("kjson"));
+        assert_eq!(v["list"][0].as_number(), Some(&Number::from(1)));
+        assert_eq!(v["list"][2].as_bool(), Some(true));
+        assert!(v["list"][3].is_null());
+        assert_eq!(v["list"][4].as_str(), Some("x\n"));
+        assert_eq!(v["nested"]["a"]["c"].as_array().map(|arr| arr.len()), Some(0));
+        assert_eq!(
+            v.as_object().unwrap().iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            ["name", "list", "nested"]
+        );
+        assert!(v.get("missing").is_none() && v["list"].get("name").is_none());
+        assert_eq!(v.to_owned(), Value::parse(json).unwrap());
+        assert_eq!(v, ArenaValue::parse(json, &arena).unwrap());
+        assert_ne!(v, v["nested"]);
+        assert_eq!(Value::from(v["list"]).to_string(), r#"[1,2.5,true,null,"x\n"]"#);
+    }
+
+    #[test]
+    fn arena_duplicate_keys() {
+        // 成员较多时查找重复的键使用哈希索引，两种情况的结果应当相同
+        let filler: String = (0..INDEXED_OBJECT_LEN).map(|i| format!(r#""k{i}": {i}, "#)).collect();
+        for prefix in ["", filler.as_str()] {
+            let json = format!(r#"{{{prefix}"a": 1, "a": "x", "c": 0, "a": [2]}}"#);
+            for duplicate_keys in [
+                DuplicateKeys::FirstWins,
+                DuplicateKeys::LastWins,
+                DuplicateKeys::CollectAll,
+            ] {
+                let options = ParseOptions {
+                    duplicate_keys,
+                    ..Default::default()
+                };
+                let arena = Arena::new();
+                let v = ArenaValue::parse_with_options(&json, options.clone(), &arena).unwrap();
+                assert_eq!(
+                    v.to_owned(),
+                    Value::parse_with_options(&json, options).unwrap(),
+                    "{json}"
+                );
+            }
+        }
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..Default::default()
+        };
+        let e = ArenaValue::parse_with_options(r#"{"a": 1, "a": 2}"#, options, &Arena::new())
+            .err()
+            .unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::DuplicateKey, 9));
+    }
+
+    #[test]
+    fn arena_reset_reuse() {
+        let json = r#"{"id": 12345678901234567890, "tags": ["a", "b\tc"], "point": {"x": 1.5, "y": -2}}"#;
+        let mut arena = Arena::with_capacity(1024);
+        let v = ArenaValue::parse(json, &arena).unwrap().to_owned();
+        let allocated = arena.allocated_bytes();
+        for _ in 0..1000 {
+            arena.reset();
+            let w = ArenaValue::parse(json, &arena).unwrap();
+            assert_eq!(w.to_owned(), v);
+        }
+        // 每次reset后都复用同一块内存，不会继续申请
+        assert_eq!(arena.allocated_bytes(), allocated);
+    }
+}
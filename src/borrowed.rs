@@ -0,0 +1,226 @@
+use crate::dict::Dict;
+use crate::error::{ParseError, ParseErrorKind, WriteError};
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParseOptions, WriteOptions};
+use crate::parser::Parser;
+use crate::value::{Events, Node, NodeRef, Value, build_from_parser, drop_tree, tree_eq};
+use std::borrow::Cow;
+use std::ops::Index;
+
+// BorrowedValue::Object使用的对象类型，后端与Map相同
+pub type BorrowedMap<'a> = Dict<Cow<'a, str>, BorrowedValue<'a>>;
+
+// 借用输入的Value：不含转义的字符串和键直接借用输入，只有含转义时才分配内存
+#[derive(Debug)]
+pub enum BorrowedValue<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<BorrowedValue<'a>>),
+    Object(BorrowedMap<'a>),
+}
+
+impl<'a> Index<&str> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        if let BorrowedValue::Object(dict) = self {
+            &dict[key]
+        } else {
+            panic!("Only BorrowedValue::Object() can be accessed by key")
+        }
+    }
+}
+
+impl<'a> Index<usize> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if let BorrowedValue::Array(arr) = self {
+            &arr[index]
+        } else {
+            panic!("Only BorrowedValue::Array() can be accessed by index")
+        }
+    }
+}
+
+impl std::fmt::Display for BorrowedValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match Value::stringify_events(Events::for_output(self), &WriteOptions::default()) {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl PartialEq for BorrowedValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        tree_eq(self, other)
+    }
+}
+
+impl Eq for BorrowedValue<'_> {}
+
+impl<'a> Node for BorrowedValue<'a> {
+    type Key = Cow<'a, str>;
+
+    fn from_array(arr: Vec<Self>) -> Self {
+        BorrowedValue::Array(arr)
+    }
+
+    fn from_object(object: BorrowedMap<'a>) -> Self {
+        BorrowedValue::Object(object)
+    }
+
+    fn from_scalar(scalar: NodeRef<'_, Self>) -> Self {
+        match scalar {
+            NodeRef::Bool(b) => BorrowedValue::Bool(b),
+            NodeRef::Number(n) => BorrowedValue::Number(n.clone()),
+            _ => BorrowedValue::Null,
+        }
+    }
+
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match self {
+            BorrowedValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    fn as_node(&self) -> NodeRef<'_, Self> {
+        match self {
+            BorrowedValue::Null => NodeRef::Null,
+            BorrowedValue::Bool(b) => NodeRef::Bool(*b),
+            BorrowedValue::Number(n) => NodeRef::Number(n),
+            BorrowedValue::String(s) => NodeRef::String(s),
+            BorrowedValue::Array(arr) => NodeRef::Array(arr),
+            BorrowedValue::Object(object) => NodeRef::Object(object),
+        }
+    }
+
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            BorrowedValue::Array(arr) => out.append(arr),
+            BorrowedValue::Object(object) => out.extend(std::mem::take(object).into_iter().map(|(_, v)| v)),
+            _ => {}
+        }
+    }
+}
+
+impl<'a> BorrowedValue<'a> {
+    // 与Value::drop_deep相同，不使用递归地释放这个值
+    pub fn drop_deep(mut self) {
+        drop_tree(&mut self);
+    }
+
+    pub fn parse(json: &'a str) -> Result<BorrowedValue<'a>, ParseError> {
+        BorrowedValue::parse_slice(json.as_bytes())
+    }
+
+    pub fn parse_slice(json: &'a [u8]) -> Result<BorrowedValue<'a>, ParseError> {
+        BorrowedValue::parse_document(Parser::new(json))
+    }
+
+    pub fn parse_with_options(json: &'a str, options: ParseOptions) -> Result<BorrowedValue<'a>, ParseError> {
+        BorrowedValue::parse_document(Parser::new(json.as_bytes()).with_options(options))
+    }
+
+    fn parse_document(mut parser: Parser<'a>) -> Result<BorrowedValue<'a>, ParseError> {
+        let v = BorrowedValue::from_parser(&mut parser)?;
+        match parser.next_event()? {
+            None => Ok(v),
+            Some(_) => Err(parser.error(ParseErrorKind::RootNotSingular)),
+        }
+    }
+
+    // 从事件流中读取下一个完整的值；只有从切片创建的Parser才能借用输入
+    pub fn from_parser(parser: &mut Parser<'a>) -> Result<BorrowedValue<'a>, ParseError> {
+        build_from_parser(
+            parser,
+            |parser| parser.borrowed_str(),
+            |parser| BorrowedValue::String(parser.borrowed_str()),
+        )
+    }
+
+    // 复制借用的字符串，转换为不再依赖输入的Value
+    pub fn into_owned(self) -> Value {
+        let owned = Value::from_events(Events::new(&self), DuplicateKeys::LastWins);
+        self.drop_deep();
+        owned
+    }
+
+    pub fn to_string_with_options(&self, options: WriteOptions) -> Result<String, WriteError> {
+        Value::stringify_events(Events::for_output(self), &options)
+    }
+}
+
+impl From<BorrowedValue<'_>> for Value {
+    fn from(value: BorrowedValue<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_unescaped_strings() {
+        let json = r#"{"name": "kjson", "escaped\n": "a\"b", "list": ["中", "\u4e2d", 1, true, null]}"#;
+        let v = BorrowedValue::parse(json).unwrap();
+        let BorrowedValue::Object(object) = &v else {
+            panic!("expected an object");
+        };
+        for (key, _) in object.iter() {
+            assert_eq!(matches!(key, Cow::Borrowed(_)), !key.contains('\n'), "{key:?}");
+        }
+        assert!(matches!(&v["name"], BorrowedValue::String(Cow::Borrowed("kjson"))));
+        assert!(matches!(&v["escaped\n"], BorrowedValue::String(Cow::Owned(s)) if s == "a\"b"));
+        assert!(matches!(&v["list"][0], BorrowedValue::String(Cow::Borrowed("中"))));
+        assert!(matches!(&v["list"][1], BorrowedValue::String(Cow::Owned(s)) if s == "中"));
+        assert_eq!(v["list"][2], BorrowedValue::Number(Number::from(1)));
+
+        // 借用的字符串指向输入本身
+        if let BorrowedValue::String(s) = &v["name"] {
+            let offset = s.as_ptr() as usize - json.as_ptr() as usize;
+            assert_eq!(&json[offset..offset + s.len()], "kjson");
+        }
+    }
+
+    #[test]
+    fn borrowed_into_owned() {
+        let json = r#"{"a": [1, 2.5, "x\ty", {"c": null}], "d": {"e": true}, "k": "v"}"#;
+        let v = BorrowedValue::parse(json).unwrap();
+        assert_eq!(v.to_string(), Value::parse(json).unwrap().to_string());
+        assert_eq!(v.into_owned(), Value::parse(json).unwrap());
+        assert_eq!(
+            Value::from(BorrowedValue::parse("\"s\"").unwrap()),
+            Value::String("s".to_string())
+        );
+    }
+
+    #[test]
+    fn borrowed_invalid_utf8() {
+        let e = BorrowedValue::parse_slice(b"[\"\xff\"]").err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::InvalidUtf8, 2));
+    }
+
+    #[test]
+    fn borrowed_duplicate_keys() {
+        let json = r#"{"a": 1, "a": "x", "a": [2]}"#;
+        let parse = |duplicate_keys| {
+            let options = ParseOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            BorrowedValue::parse_with_options(json, options).unwrap().into_owned()
+        };
+        assert_eq!(parse(DuplicateKeys::FirstWins), Value::parse(r#"{"a": 1}"#).unwrap());
+        assert_eq!(parse(DuplicateKeys::LastWins), Value::parse(r#"{"a": [2]}"#).unwrap());
+        assert_eq!(
+            parse(DuplicateKeys::CollectAll),
+            Value::parse(r#"{"a": [1, "x", [2]]}"#).unwrap()
+        );
+    }
+}
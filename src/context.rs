@@ -1,15 +1,243 @@
+use crate::error::{ParseError, ParseErrorKind, PathSegment};
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+// 解析过程中的路径；键尽量借用输入，出错时才转换为PathSegment
+pub enum Segment<'a> {
+    Index(usize),
+    Key(Cow<'a, str>),
+}
+
+// 从Read读取时每次补充的字节数
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
 pub struct Context<'a> {
-    pub bytes: &'a [u8],
+    // 从切片解析时借用整个输入；从Read解析时是可补充的缓冲区
+    data: Cow<'a, [u8]>,
+    pos: usize,
+    reader: Option<Box<dyn Read + 'a>>,
+    // 已从缓冲区头部丢弃的字节数，以及其中的换行数和最后一行的字符数，用于计算行列号
+    discarded: usize,
+    discarded_lines: usize,
+    discarded_columns: usize,
+    pub path: Vec<Segment<'a>>,
+    // 允许的输入总字节数
+    pub max_size: usize,
+    // 正在记录原文的起始偏移，以及记录期间已从缓冲区丢弃的那部分原文
+    capture: Option<usize>,
+    captured: Vec<u8>,
 }
 
 impl<'a> Context<'a> {
     pub fn new(json: &'a [u8]) -> Self {
-        Self { bytes: json }
+        Self {
+            data: Cow::Borrowed(json),
+            pos: 0,
+            reader: None,
+            discarded: 0,
+            discarded_lines: 0,
+            discarded_columns: 0,
+            path: Vec::new(),
+            max_size: usize::MAX,
+            capture: None,
+            captured: Vec::new(),
+        }
+    }
+
+    pub fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        Self {
+            data: Cow::Owned(Vec::new()),
+            reader: Some(Box::new(reader)),
+            ..Self::new(&[])
+        }
+    }
+
+    // 缓冲区中尚未解析的字节
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[self.pos..]
+    }
+
+    // 从切片解析时，以输入的生命周期借用尚未解析的字节
+    pub fn borrowed_bytes(&self) -> Option<&'a [u8]> {
+        match self.data {
+            Cow::Borrowed(data) => Some(&data[self.pos..]),
+            Cow::Owned(_) => None,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.discarded + self.pos
+    }
+
+    pub fn advance(&mut self, n: usize) {
+        self.pos += n;
     }
 
     pub fn step(&mut self) -> Option<u8> {
-        let &b = self.bytes.first()?;
-        self.bytes = &self.bytes[1..];
+        let &b = self.data.get(self.pos)?;
+        self.pos += 1;
         Some(b)
     }
+
+    // 从reader补充缓冲区，返回是否读到了新数据；切片输入总是返回false
+    pub fn fill(&mut self) -> Result<bool, ParseError> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(false);
+        };
+        let Cow::Owned(buf) = &mut self.data else {
+            return Ok(false);
+        };
+        // 已解析的部分不再需要，丢弃以免缓冲区无限增长
+        if self.pos > 0 && self.pos >= buf.len() / 2 {
+            let consumed = &buf[..self.pos];
+            match consumed.iter().rposition(|&b| b == b'\n') {
+                Some(i) => {
+                    self.discarded_lines += consumed.iter().filter(|&&b| b == b'\n').count();
+                    self.discarded_columns = Context::count_chars(&consumed[i + 1..]);
+                }
+                None => self.discarded_columns += Context::count_chars(consumed),
+            }
+            if let Some(start) = self.capture {
+                let from = start.saturating_sub(self.discarded).min(self.pos);
+                self.captured.extend_from_slice(&buf[from..self.pos]);
+            }
+            self.discarded += self.pos;
+            buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(n) => {
+                    buf.extend_from_slice(&chunk[..n]);
+                    self.check_size()?;
+                    return Ok(n > 0);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.error(ParseErrorKind::Io(e.kind()))),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    // 从当前位置开始记录原文，之前的记录被丢弃
+    pub fn start_capture(&mut self) {
+        self.capture = Some(self.offset());
+        self.captured.clear();
+    }
+
+    #[cfg(feature = "serde")]
+    // 结束记录，返回从start_capture到当前位置之间的原文
+    pub fn end_capture(&mut self) -> Vec<u8> {
+        let start = self.capture.take().unwrap_or(self.offset());
+        let mut bytes = std::mem::take(&mut self.captured);
+        let from = start.saturating_sub(self.discarded).min(self.pos);
+        bytes.extend_from_slice(&self.data[from..self.pos]);
+        bytes
+    }
+
+    #[cfg(feature = "serde")]
+    // 读入全部输入后不再使用reader，缓冲区从此不会丢弃已解析的部分
+    pub fn read_to_end(&mut self) -> Result<(), ParseError> {
+        while self.fill()? {}
+        self.reader = None;
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    // 从offset开始、仍在缓冲区中的输入；offset之前的部分已被丢弃时返回None
+    pub fn buffered_from(&self, offset: usize) -> Option<&[u8]> {
+        self.data.get(offset.checked_sub(self.discarded)?..)
+    }
+
+    // 检查目前已知的输入是否超过max_size
+    pub fn check_size(&self) -> Result<(), ParseError> {
+        if self.discarded + self.data.len() > self.max_size {
+            return Err(self.error_at(self.max_size, ParseErrorKind::DocumentTooLarge));
+        }
+        Ok(())
+    }
+
+    // 保证从当前位置起至少有n个字节在缓冲区中（输入足够长时）
+    pub fn ensure(&mut self, n: usize) -> Result<(), ParseError> {
+        while self.bytes().len() < n && self.fill()? {}
+        Ok(())
+    }
+
+    // 保证从当前位置起满足pred的连续字节全部在缓冲区中
+    pub fn ensure_while(&mut self, pred: impl Fn(u8) -> bool) -> Result<(), ParseError> {
+        let mut scanned = 0;
+        loop {
+            let bytes = self.bytes();
+            match bytes[scanned..].iter().position(|&b| !pred(b)) {
+                Some(_) => return Ok(()),
+                None => scanned = bytes.len(),
+            }
+            if !self.fill()? {
+                return Ok(());
+            }
+        }
+    }
+
+    // 保证当前位置开始的整个字符串（直到未转义的右引号）都在缓冲区中；
+    // 已读入的字节数超过max_bytes时不再继续读取
+    pub fn ensure_string(&mut self, max_bytes: usize) -> Result<(), ParseError> {
+        if self.reader.is_none() {
+            return Ok(());
+        }
+        let mut i = 1;
+        loop {
+            let bytes = self.bytes();
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'"' => return Ok(()),
+                    b'\\' => i += 2,
+                    _ => i += 1,
+                }
+            }
+            if i > max_bytes || !self.fill()? {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn error(&self, kind: ParseErrorKind) -> ParseError {
+        self.error_at(self.offset(), kind)
+    }
+
+    pub fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        let (line, column) = self.line_column(offset);
+        let path = self
+            .path
+            .iter()
+            .map(|segment| match segment {
+                Segment::Index(i) => PathSegment::Index(*i),
+                Segment::Key(key) => PathSegment::Key(key.to_string()),
+            })
+            .collect();
+        ParseError::new(kind, offset, line, column, path)
+    }
+
+    // 由已解析的字节计算行号和列号（列号按UTF-8字符计数）
+    fn line_column(&self, offset: usize) -> (usize, usize) {
+        let end = offset.saturating_sub(self.discarded).min(self.data.len());
+        let consumed = &self.data[..end];
+        match consumed.iter().rposition(|&b| b == b'\n') {
+            Some(i) => {
+                let lines = consumed.iter().filter(|&&b| b == b'\n').count();
+                (
+                    self.discarded_lines + lines + 1,
+                    Context::count_chars(&consumed[i + 1..]) + 1,
+                )
+            }
+            None => (
+                self.discarded_lines + 1,
+                self.discarded_columns + Context::count_chars(consumed) + 1,
+            ),
+        }
+    }
+
+    fn count_chars(bytes: &[u8]) -> usize {
+        bytes.iter().filter(|&&b| (b & 0xC0) != 0x80).count()
+    }
 }
@@ -0,0 +1,232 @@
+use crate::error::{ParseError, ParseErrorKind};
+use std::fmt::{self, Display};
+
+// 将ParseError与原始输入结合，渲染出带源码片段和插入符(^)的诊断信息:
+//
+// error: expected `,` or `]` at line 2 column 11
+//  --> $.a
+//   |
+// 2 |   "a": [1 2]
+//   |           ^ expected `,` or `]`, found `2`
+//   = hint: did you forget a `,` between elements?
+pub struct Diagnostic<'a> {
+    error: &'a ParseError,
+    source: &'a str,
+}
+
+impl ParseError {
+    pub fn diagnostic<'a>(&'a self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic { error: self, source }
+    }
+}
+
+impl<'a> Diagnostic<'a> {
+    // 出错位置处实际遇到的记号
+    pub fn found(&self) -> String {
+        let offset = self.offset();
+        let rest = &self.source[offset..];
+        let first = match rest.chars().next() {
+            Some(c) => c,
+            None => return String::from("end of input"),
+        };
+        let token: String = match first {
+            '\\' => rest.chars().take(2).collect(),
+            c if c.is_alphanumeric() || c == '-' || c == '+' || c == '.' => rest
+                .chars()
+                .take_while(|&c| c.is_alphanumeric() || c == '-' || c == '+' || c == '.')
+                .take(16)
+                .collect(),
+            c if c.is_control() => return format!("control character U+{:04X}", c as u32),
+            c => c.to_string(),
+        };
+        format!("`{token}`")
+    }
+
+    pub fn expected(&self) -> &'static str {
+        match self.error.kind() {
+            ParseErrorKind::ExpectValue | ParseErrorKind::InvalidValue => "a value",
+            ParseErrorKind::RootNotSingular => "end of input",
+            ParseErrorKind::NumberTooBig => "a number that fits in a 64-bit float",
+            ParseErrorKind::MissQuotationMark => "`\"`",
+            ParseErrorKind::InvalidStringEscape => "an escape sequence",
+            ParseErrorKind::InvalidStringChar => "a printable character",
+            ParseErrorKind::InvalidUtf8 => "valid UTF-8",
+            ParseErrorKind::InvalidUnicodeHex => "4 hex digits after `\\u`",
+            ParseErrorKind::InvalidUnicodeSurrogate => "a valid surrogate pair",
+            ParseErrorKind::MissCommaOrSquareBracket => "`,` or `]`",
+            ParseErrorKind::MissKey => "a string key",
+            ParseErrorKind::MissColon => "`:`",
+            ParseErrorKind::MissCommaOrCurlyBracket => "`,` or `}`",
+            ParseErrorKind::DepthLimitExceeded => "a less deeply nested value",
+            ParseErrorKind::DocumentTooLarge => "a smaller document",
+            ParseErrorKind::StringTooLong => "a shorter string",
+            ParseErrorKind::ArrayTooLong => "`]` after fewer elements",
+            ParseErrorKind::ObjectTooLong => "`}` after fewer members",
+            ParseErrorKind::TooManyNodes => "fewer values in the document",
+            ParseErrorKind::DuplicateKey => "a key not used before in this object",
+            ParseErrorKind::Io(_) => "more input",
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        let offset = self.offset();
+        let found = self.source[offset..].chars().next();
+        let previous = self.source[..offset].bytes().rev().find(|b| !b" \t\r\n".contains(b));
+        let trailing_comma = previous == Some(b',') && matches!(found, Some(']') | Some('}'));
+        match self.error.kind() {
+            ParseErrorKind::ExpectValue | ParseErrorKind::InvalidValue | ParseErrorKind::MissKey if trailing_comma => {
+                Some("trailing comma not allowed")
+            }
+            ParseErrorKind::InvalidValue | ParseErrorKind::MissKey if found == Some('\'') => {
+                Some("strings must use double quotes")
+            }
+            ParseErrorKind::InvalidValue => match found {
+                Some('+') | Some('.') => Some("numbers must start with a digit or `-`"),
+                Some('N') | Some('I') | Some('n') | Some('i')
+                    if self.source[offset..].to_ascii_lowercase().starts_with("nan")
+                        || self.source[offset..].to_ascii_lowercase().starts_with("inf") =>
+                {
+                    Some("NaN and Infinity are not valid JSON numbers")
+                }
+                Some('/') => Some("comments are not allowed in JSON"),
+                _ => None,
+            },
+            ParseErrorKind::ExpectValue => Some("the input is empty"),
+            ParseErrorKind::MissKey => Some("object keys must be strings in double quotes"),
+            ParseErrorKind::RootNotSingular => match previous {
+                Some(b'0') if found.is_some_and(|c| c.is_ascii_digit()) => Some("numbers cannot have leading zeros"),
+                _ => Some("only one root value is allowed"),
+            },
+            ParseErrorKind::MissCommaOrSquareBracket => match found {
+                None => Some("did you forget a closing `]`?"),
+                Some('}') => Some("mismatched brackets: this array was opened with `[`"),
+                _ => Some("did you forget a `,` between elements?"),
+            },
+            ParseErrorKind::MissCommaOrCurlyBracket => match found {
+                None => Some("did you forget a closing `}`?"),
+                Some(']') => Some("mismatched brackets: this object was opened with `{`"),
+                _ => Some("did you forget a `,` between members?"),
+            },
+            ParseErrorKind::MissColon => Some("did you forget a `:` after the key?"),
+            ParseErrorKind::MissQuotationMark => Some("did you forget a closing `\"`?"),
+            ParseErrorKind::InvalidStringEscape => Some(r#"valid escapes are \" \\ \/ \b \f \n \r \t and \uXXXX"#),
+            ParseErrorKind::InvalidStringChar => Some("control characters in strings must be escaped"),
+            ParseErrorKind::InvalidUtf8 => Some("JSON text must be encoded as UTF-8"),
+            ParseErrorKind::InvalidUnicodeHex => None,
+            ParseErrorKind::InvalidUnicodeSurrogate => {
+                Some(r"a high surrogate \uD800-\uDBFF must be followed by a low surrogate \uDC00-\uDFFF")
+            }
+            ParseErrorKind::DepthLimitExceeded => {
+                Some("raise `ParseOptions::max_depth` if such deep nesting is expected")
+            }
+            ParseErrorKind::DocumentTooLarge => Some("the limit is set by `ParseOptions::max_document_size`"),
+            ParseErrorKind::StringTooLong => Some("the limit is set by `ParseOptions::max_string_length`"),
+            ParseErrorKind::ArrayTooLong => Some("the limit is set by `ParseOptions::max_array_length`"),
+            ParseErrorKind::ObjectTooLong => Some("the limit is set by `ParseOptions::max_object_length`"),
+            ParseErrorKind::TooManyNodes => Some("the limit is set by `ParseOptions::max_nodes`"),
+            ParseErrorKind::DuplicateKey => {
+                Some("duplicate keys are rejected because of `ParseOptions::duplicate_keys`")
+            }
+            ParseErrorKind::NumberTooBig | ParseErrorKind::Io(_) => None,
+        }
+    }
+
+    // 防止offset超出source或落在UTF-8字符中间
+    fn offset(&self) -> usize {
+        let mut offset = self.error.offset().min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = self.offset();
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |i| offset + i);
+        let line = self.source[line_start..line_end].trim_end_matches('\r');
+        let line_no = self.error.line().to_string();
+        let gutter = " ".repeat(line_no.len());
+        // 制表符原样保留，使插入符与源码对齐
+        let padding: String = self.source[line_start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(
+            f,
+            "error: {} at line {} column {}",
+            self.error.kind(),
+            self.error.line(),
+            self.error.column()
+        )?;
+        writeln!(f, "{gutter}--> {}", self.error.path_string())?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line_no} | {line}")?;
+        write!(
+            f,
+            "{gutter} | {padding}^ expected {}, found {}",
+            self.expected(),
+            self.found()
+        )?;
+        if let Some(hint) = self.hint() {
+            write!(f, "\n{gutter} = hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    fn render(json: &str) -> String {
+        Value::parse(json).err().unwrap().diagnostic(json).to_string()
+    }
+
+    #[test]
+    fn render_snippet_and_caret() {
+        let json = "{\n  \"a\": [1 2]\n}";
+        let expected = [
+            "error: expected `,` or `]` at line 2 column 11",
+            " --> $.a",
+            "  |",
+            "2 |   \"a\": [1 2]",
+            "  |           ^ expected `,` or `]`, found `2`",
+            "  = hint: did you forget a `,` between elements?",
+        ];
+        assert_eq!(render(json), expected.join("\n"));
+    }
+
+    #[test]
+    fn render_hints() {
+        let hint = |json: &str| Value::parse(json).err().unwrap().diagnostic(json).hint();
+        assert_eq!(hint("[1, 2,]"), Some("trailing comma not allowed"));
+        assert_eq!(hint(r#"{"a": 1,}"#), Some("trailing comma not allowed"));
+        assert_eq!(hint("[1, [2, 3]"), Some("did you forget a closing `]`?"));
+        assert_eq!(hint(r#"{"a": {"b": 1}"#), Some("did you forget a closing `}`?"));
+        assert_eq!(hint("['a']"), Some("strings must use double quotes"));
+        assert_eq!(hint("NaN"), Some("NaN and Infinity are not valid JSON numbers"));
+        assert_eq!(hint("0123"), Some("numbers cannot have leading zeros"));
+    }
+
+    #[test]
+    fn render_found_token() {
+        let found = |json: &str| Value::parse(json).err().unwrap().diagnostic(json).found();
+        assert_eq!(found("[1, tru]"), "`tru`");
+        assert_eq!(found("[1, 2"), "end of input");
+        assert_eq!(found(r#""\q""#), r"`\q`");
+        assert_eq!(found("\"\x01\""), "control character U+0001");
+    }
+
+    #[test]
+    fn render_keeps_tabs_aligned() {
+        let json = "[\n\t\t1,\n\t\tnul\n]";
+        let text = render(json);
+        assert!(text.contains("3 | \t\tnul\n  | \t\t^ expected a value, found `nul`"));
+    }
+}
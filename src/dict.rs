@@ -1,75 +1,716 @@
 use std::{
     borrow::Borrow,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    marker::PhantomData,
     ops::{Deref, DerefMut, Index, IndexMut},
 };
 
+// 所有后端对键的共同要求
+pub trait Key: Ord + Hash {}
+
+impl<T: Ord + Hash + ?Sized> Key for T {}
+
+// Dict的存储后端。迭代顺序：BTreeMap和VecMap按键排序，IndexMap按插入顺序，HashMap不确定
+pub trait Backend<K, V>: Default {
+    // 迭代顺序是否确定；不确定时输出JSON之前先按键排序
+    const ORDERED: bool = true;
+
+    type Iter<'a>: Iterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+    type IterMut<'a>: Iterator<Item = (&'a K, &'a mut V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+    type IntoIter: Iterator<Item = (K, V)>;
+
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized;
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized;
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+    // 插入或替换，返回新值的可变引用，供Entry使用
+    fn insert_and_get_mut(&mut self, key: K, value: V) -> &mut V;
+    // 删除后其余键保持原有顺序
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized;
+    fn clear(&mut self);
+    fn iter(&self) -> Self::Iter<'_>;
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+    fn into_iter(self) -> Self::IntoIter;
+}
+
+impl<K: Key, V> Backend<K, V> for BTreeMap<K, V> {
+    type Iter<'a>
+        = std::collections::btree_map::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IterMut<'a>
+        = std::collections::btree_map::IterMut<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IntoIter = std::collections::btree_map::IntoIter<K, V>;
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        BTreeMap::get(self, key)
+    }
+
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn insert_and_get_mut(&mut self, key: K, value: V) -> &mut V {
+        match BTreeMap::entry(self, key) {
+            std::collections::btree_map::Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_mut()
+            }
+            std::collections::btree_map::Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        BTreeMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        BTreeMap::clear(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        BTreeMap::iter_mut(self)
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self)
+    }
+}
+
+// 按哈希顺序迭代，不做排序；输出JSON时由写出的一方按键排序
+impl<K: Key, V> Backend<K, V> for HashMap<K, V> {
+    const ORDERED: bool = false;
+
+    type Iter<'a>
+        = std::collections::hash_map::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IterMut<'a>
+        = std::collections::hash_map::IterMut<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IntoIter = std::collections::hash_map::IntoIter<K, V>;
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn insert_and_get_mut(&mut self, key: K, value: V) -> &mut V {
+        match HashMap::entry(self, key) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_mut()
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        HashMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        HashMap::iter_mut(self)
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self)
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+impl<K: Key, V> Backend<K, V> for indexmap::IndexMap<K, V> {
+    type Iter<'a>
+        = indexmap::map::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IterMut<'a>
+        = indexmap::map::IterMut<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type IntoIter = indexmap::map::IntoIter<K, V>;
+
+    fn len(&self) -> usize {
+        indexmap::IndexMap::len(self)
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        indexmap::IndexMap::get(self, key)
+    }
+
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        indexmap::IndexMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        indexmap::IndexMap::insert(self, key, value)
+    }
+
+    fn insert_and_get_mut(&mut self, key: K, value: V) -> &mut V {
+        match indexmap::IndexMap::entry(self, key) {
+            indexmap::map::Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_mut()
+            }
+            indexmap::map::Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        indexmap::IndexMap::shift_remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        indexmap::IndexMap::clear(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        indexmap::IndexMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        indexmap::IndexMap::iter_mut(self)
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self)
+    }
+}
+
+// 按键排序的键值对数组，二分查找；成员少时比树和哈希表更省内存
 #[derive(Debug, Clone)]
-pub struct Dict<K, V> {
-    data: BTreeMap<K, V>,
+pub struct VecMap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Default for VecMap<K, V> {
+    fn default() -> Self {
+        VecMap { entries: Vec::new() }
+    }
 }
 
-impl<K, V> Deref for Dict<K, V> {
-    type Target = BTreeMap<K, V>;
+impl<K: Key, V> VecMap<K, V> {
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.entries.binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+}
+
+impl<K: Key, V> Backend<K, V> for VecMap<K, V> {
+    type Iter<'a>
+        = std::iter::Map<std::slice::Iter<'a, (K, V)>, fn(&'a (K, V)) -> (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a;
+    type IterMut<'a>
+        = std::iter::Map<std::slice::IterMut<'a, (K, V)>, fn(&'a mut (K, V)) -> (&'a K, &'a mut V)>
+    where
+        K: 'a,
+        V: 'a;
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.search(key).ok().map(|i| &self.entries[i].1)
+    }
+
+    fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.search(key).ok().map(|i| &mut self.entries[i].1)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            Err(i) => {
+                self.entries.insert(i, (key, value));
+                None
+            }
+        }
+    }
+
+    fn insert_and_get_mut(&mut self, key: K, value: V) -> &mut V {
+        let i = match self.search(&key) {
+            Ok(i) => {
+                self.entries[i].1 = value;
+                i
+            }
+            Err(i) => {
+                self.entries.insert(i, (key, value));
+                i
+            }
+        };
+        &mut self.entries[i].1
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.search(key).ok().map(|i| self.entries.remove(i).1)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+// Value使用的后端：同时启用多个后端特性时preserve_order优先，其次hash_map、sorted_vec，都未启用时为BTreeMap；
+// 特性可以叠加，因此不报错，优先级同时写在Cargo.toml中
+#[cfg(feature = "preserve_order")]
+pub type DefaultBackend<K, V> = indexmap::IndexMap<K, V>;
+#[cfg(all(not(feature = "preserve_order"), feature = "hash_map"))]
+pub type DefaultBackend<K, V> = HashMap<K, V>;
+#[cfg(all(not(feature = "preserve_order"), not(feature = "hash_map"), feature = "sorted_vec"))]
+pub type DefaultBackend<K, V> = VecMap<K, V>;
+#[cfg(not(any(feature = "preserve_order", feature = "hash_map", feature = "sorted_vec")))]
+pub type DefaultBackend<K, V> = BTreeMap<K, V>;
+
+pub type Iter<'a, K, V, M = DefaultBackend<K, V>> = <M as Backend<K, V>>::Iter<'a>;
+
+#[derive(Debug, Clone)]
+pub struct Dict<K, V, M = DefaultBackend<K, V>> {
+    data: M,
+    marker: PhantomData<(K, V)>,
+}
+
+// 可以直接使用后端自身的方法，例如默认的BTreeMap后端上的range、first_key_value；
+// 与Dict同名的方法仍以Dict的为准
+impl<K, V, M> Deref for Dict<K, V, M> {
+    type Target = M;
 
     fn deref(&self) -> &Self::Target {
         &self.data
     }
 }
 
-impl<K, V> DerefMut for Dict<K, V> {
+impl<K, V, M> DerefMut for Dict<K, V, M> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.data
     }
 }
 
-impl<K, V> PartialEq for Dict<K, V>
+// 与顺序无关：键集合相同且对应的值相等
+impl<K, V, M> PartialEq for Dict<K, V, M>
 where
-    K: Ord,
+    K: Key,
     V: PartialEq,
+    M: Backend<K, V>,
 {
     fn eq(&self, other: &Self) -> bool {
-        let mut result = true;
-        if self.len() != other.len() {
-            result = false;
-        } else {
-            for key in self.keys() {
-                result = result && (self[key] == other[key]);
-            }
-        }
-        result
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
     }
 }
 
-impl<K, Q, V> Index<&Q> for Dict<K, V>
+impl<K, Q, V, M> Index<&Q> for Dict<K, V, M>
 where
-    K: Borrow<Q> + Ord,
-    Q: Ord + ?Sized,
+    K: Borrow<Q> + Key,
+    Q: Key + ?Sized,
+    M: Backend<K, V>,
 {
     type Output = V;
 
     fn index(&self, key: &Q) -> &Self::Output {
-        &self.data[key]
+        self.get(key).expect("key not found in Dict")
     }
 }
 
-impl<K, Q, V> IndexMut<&Q> for Dict<K, V>
+impl<K, Q, V, M> IndexMut<&Q> for Dict<K, V, M>
 where
-    K: Borrow<Q> + Ord,
-    Q: Ord + ?Sized,
+    K: Borrow<Q> + Key,
+    Q: Key + ?Sized,
+    M: Backend<K, V>,
 {
     fn index_mut(&mut self, key: &Q) -> &mut Self::Output {
-        self.data.get_mut(key).unwrap()
+        self.get_mut(key).expect("key not found in Dict")
     }
 }
 
-impl<K, V> Dict<K, V> {
+impl<K: Key, V, M: Backend<K, V>> IntoIterator for Dict<K, V, M> {
+    type Item = (K, V);
+    type IntoIter = M::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a, K: Key, V, M: Backend<K, V>> IntoIterator for &'a Dict<K, V, M> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = M::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> FromIterator<(K, V)> for Dict<K, V, M> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut dict = Dict::<K, V, M>::default();
+        for (key, value) in iter {
+            dict.insert(key, value);
+        }
+        dict
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> Extend<(K, V)> for Dict<K, V, M> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>, const N: usize> From<[(K, V); N]> for Dict<K, V, M> {
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> From<BTreeMap<K, V>> for Dict<K, V, M> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        IntoIterator::into_iter(map).collect()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> From<HashMap<K, V>> for Dict<K, V, M> {
+    fn from(map: HashMap<K, V>) -> Self {
+        IntoIterator::into_iter(map).collect()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> From<Dict<K, V, M>> for BTreeMap<K, V> {
+    fn from(dict: Dict<K, V, M>) -> Self {
+        dict.into_iter().collect()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> From<Dict<K, V, M>> for HashMap<K, V> {
+    fn from(dict: Dict<K, V, M>) -> Self {
+        dict.into_iter().collect()
+    }
+}
+
+impl<K, V, M: Default> Default for Dict<K, V, M> {
+    fn default() -> Self {
+        Dict {
+            data: M::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Key, V> Dict<K, V> {
     pub fn new() -> Self {
-        Self { data: BTreeMap::new() }
+        Self::default()
     }
 
     pub fn from_btree_map(btree_map: BTreeMap<K, V>) -> Self {
-        Dict { data: btree_map }
+        btree_map.into()
+    }
+}
+
+impl<K: Key, V, M: Backend<K, V>> Dict<K, V, M> {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.data.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.data.get_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    // 键已存在时替换值并返回旧值，键的位置不变
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.data.insert(key, value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Key + ?Sized,
+    {
+        self.data.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear()
+    }
+
+    pub fn iter(&self) -> M::Iter<'_> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> M::IterMut<'_> {
+        self.data.iter_mut()
+    }
+
+    // 输出JSON时的成员顺序：迭代顺序不确定的后端返回按键排序后的成员，其余返回None，直接用iter
+    pub(crate) fn sorted_entries(&self) -> Option<Vec<(&K, &V)>> {
+        if M::ORDERED {
+            return None;
+        }
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        Some(entries)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, M> {
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry { dict: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { dict: self, key })
+        }
+    }
+}
+
+// 与std的Entry用法相同；为了适配所有后端，操作时按键重新查找
+pub enum Entry<'a, K, V, M = DefaultBackend<K, V>> {
+    Occupied(OccupiedEntry<'a, K, V, M>),
+    Vacant(VacantEntry<'a, K, V, M>),
+}
+
+pub struct OccupiedEntry<'a, K, V, M = DefaultBackend<K, V>> {
+    dict: &'a mut Dict<K, V, M>,
+    key: K,
+}
+
+pub struct VacantEntry<'a, K, V, M = DefaultBackend<K, V>> {
+    dict: &'a mut Dict<K, V, M>,
+    key: K,
+}
+
+impl<'a, K: Key, V, M: Backend<K, V>> Entry<'a, K, V, M> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Key, V, M: Backend<K, V>> OccupiedEntry<'a, K, V, M> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.dict[&self.key]
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.dict[&self.key]
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.dict[&self.key]
+    }
+
+    // 替换值并返回旧值
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.dict.remove(&self.key).expect("occupied entry has a value")
+    }
+}
+
+impl<'a, K: Key, V, M: Backend<K, V>> VacantEntry<'a, K, V, M> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.dict.data.insert_and_get_mut(self.key, value)
     }
 }
 
@@ -85,4 +726,132 @@ mod tests {
         d2.insert("1".to_string(), 1);
         assert_eq!(d1, d2)
     }
+
+    #[test]
+    fn deref_to_backend() {
+        let mut d = Dict::<String, i64, BTreeMap<String, i64>>::from([("b".to_string(), 2), ("a".to_string(), 1)]);
+        assert_eq!(d.first_key_value(), Some((&"a".to_string(), &1)));
+        assert_eq!(d.range("b".to_string()..).count(), 1);
+        d.retain(|_, v| *v > 1);
+        assert_eq!(d.len(), 1);
+    }
+
+    // 每个后端跑同一组测试；order是迭代顺序：sorted按键排序，insertion按插入顺序，hash不确定
+    macro_rules! backend_tests {
+        ($name:ident, $backend:ty, order: $order:ident) => {
+            mod $name {
+                use super::*;
+
+                type D = Dict<String, i64, $backend>;
+
+                fn dict(entries: &[(&str, i64)]) -> D {
+                    entries.iter().map(|&(k, v)| (k.to_string(), v)).collect()
+                }
+
+                #[test]
+                fn insert_get_remove() {
+                    let mut d = D::default();
+                    assert!(d.is_empty());
+                    assert_eq!(d.insert("b".to_string(), 1), None);
+                    assert_eq!(d.insert("a".to_string(), 2), None);
+                    assert_eq!(d.insert("b".to_string(), 3), Some(1));
+                    assert_eq!(d.len(), 2);
+                    assert_eq!(d.get("b"), Some(&3));
+                    assert!(d.contains_key("a") && !d.contains_key("c"));
+                    d["a"] += 10;
+                    assert_eq!(d["a"], 12);
+                    *d.get_mut("b").unwrap() = 4;
+                    assert_eq!(d.remove("b"), Some(4));
+                    assert_eq!(d.remove("b"), None);
+                    assert_eq!(d, dict(&[("a", 12)]));
+                    d.clear();
+                    assert!(d.is_empty());
+                }
+
+                #[test]
+                fn entry() {
+                    let mut d = dict(&[("a", 1)]);
+                    *d.entry("a".to_string()).or_insert(10) += 1;
+                    *d.entry("b".to_string()).or_insert(10) += 1;
+                    d.entry("c".to_string()).and_modify(|v| *v = 0).or_default();
+                    d.entry("a".to_string()).and_modify(|v| *v *= 3);
+                    assert_eq!(d, dict(&[("a", 6), ("b", 11), ("c", 0)]));
+                    match d.entry("b".to_string()) {
+                        Entry::Occupied(mut entry) => {
+                            assert_eq!((entry.key().as_str(), *entry.get()), ("b", 11));
+                            assert_eq!(entry.insert(12), 11);
+                            assert_eq!(entry.remove(), 12);
+                        }
+                        Entry::Vacant(_) => unreachable!(),
+                    }
+                    match d.entry("d".to_string()) {
+                        Entry::Vacant(entry) => assert_eq!(*entry.insert(4), 4),
+                        Entry::Occupied(_) => unreachable!(),
+                    }
+                    assert_eq!(d, dict(&[("a", 6), ("c", 0), ("d", 4)]));
+                }
+
+                #[test]
+                fn conversions() {
+                    let d = dict(&[("a", 1), ("b", 2)]);
+                    let btree: BTreeMap<String, i64> = d.clone().into();
+                    let hash: HashMap<String, i64> = d.clone().into();
+                    assert_eq!(D::from(btree.clone()), d);
+                    assert_eq!(D::from(hash), d);
+                    assert_eq!(D::from([("b".to_string(), 2), ("a".to_string(), 1)]), d);
+                    let mut e = D::default();
+                    e.extend(btree);
+                    assert_eq!(e, d);
+                }
+
+                #[test]
+                fn eq_ignores_order() {
+                    assert_eq!(dict(&[("a", 1), ("b", 2)]), dict(&[("b", 2), ("a", 1)]));
+                    assert_ne!(dict(&[("a", 1), ("b", 2)]), dict(&[("a", 1), ("c", 2)]));
+                    assert_ne!(dict(&[("a", 1)]), dict(&[("a", 2)]));
+                    assert_ne!(dict(&[("a", 1)]), dict(&[("a", 1), ("b", 2)]));
+                }
+
+                #[test]
+                fn iteration_order() {
+                    let mut d = dict(&[("z", 1), ("a", 2), ("m", 3), ("b", 4)]);
+                    d.insert("z".to_string(), 5);
+                    d.remove("a");
+                    for v in d.values_mut() {
+                        *v *= 10;
+                    }
+                    let order = stringify!($order);
+                    let expected = if order == "insertion" {
+                        [("z", 50), ("m", 30), ("b", 40)]
+                    } else {
+                        [("b", 40), ("m", 30), ("z", 50)]
+                    };
+                    // 哈希顺序不确定，排序之后再比较
+                    fn normalize<T: Ord>(mut entries: Vec<T>) -> Vec<T> {
+                        if stringify!($order) == "hash" {
+                            entries.sort();
+                        }
+                        entries
+                    }
+                    let entries: Vec<_> = d.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+                    assert_eq!(normalize(entries), expected);
+                    let keys: Vec<_> = d.keys().map(|k| (k.as_str(), 0)).collect();
+                    assert_eq!(normalize(keys), expected.map(|(k, _)| (k, 0)));
+                    // 只有顺序不确定的后端在输出时需要排序
+                    let sorted = d
+                        .sorted_entries()
+                        .map(|e| e.into_iter().map(|(k, v)| (k.as_str(), *v)).collect());
+                    assert_eq!(sorted, (order == "hash").then(|| expected.to_vec()));
+                    let entries: Vec<_> = d.into_iter().collect();
+                    assert_eq!(normalize(entries), expected.map(|(k, v)| (k.to_string(), v)));
+                }
+            }
+        };
+    }
+
+    backend_tests!(btree_map, BTreeMap<String, i64>, order: sorted);
+    backend_tests!(hash_map, HashMap<String, i64>, order: hash);
+    backend_tests!(vec_map, VecMap<String, i64>, order: sorted);
+    #[cfg(feature = "preserve_order")]
+    backend_tests!(index_map, indexmap::IndexMap<String, i64>, order: insertion);
 }
@@ -1,5 +1,8 @@
-#[derive(PartialEq, Debug)]
-pub enum ParseError {
+use std::fmt::{self, Display};
+use std::io;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ParseErrorKind {
     ExpectValue,
     InvalidValue,
     RootNotSingular,
@@ -7,10 +10,151 @@ pub enum ParseError {
     MissQuotationMark,
     InvalidStringEscape,
     InvalidStringChar,
+    InvalidUtf8,
     InvalidUnicodeHex,
     InvalidUnicodeSurrogate,
     MissCommaOrSquareBracket,
     MissKey,
     MissColon,
     MissCommaOrCurlyBracket,
+    DepthLimitExceeded,
+    DocumentTooLarge,
+    StringTooLong,
+    ArrayTooLong,
+    ObjectTooLong,
+    TooManyNodes,
+    DuplicateKey,
+    Io(io::ErrorKind),
 }
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ParseErrorKind::Io(kind) => return write!(f, "I/O error: {kind}"),
+            ParseErrorKind::ExpectValue => "expected a value",
+            ParseErrorKind::InvalidValue => "invalid value",
+            ParseErrorKind::RootNotSingular => "unexpected characters after the root value",
+            ParseErrorKind::NumberTooBig => "number out of range",
+            ParseErrorKind::MissQuotationMark => "missing quotation mark",
+            ParseErrorKind::InvalidStringEscape => "invalid escape sequence in string",
+            ParseErrorKind::InvalidStringChar => "invalid control character in string",
+            ParseErrorKind::InvalidUtf8 => "invalid UTF-8 in string",
+            ParseErrorKind::InvalidUnicodeHex => "invalid \\u hex escape",
+            ParseErrorKind::InvalidUnicodeSurrogate => "invalid unicode surrogate",
+            ParseErrorKind::MissCommaOrSquareBracket => "expected `,` or `]`",
+            ParseErrorKind::MissKey => "expected a string key",
+            ParseErrorKind::MissColon => "expected `:`",
+            ParseErrorKind::MissCommaOrCurlyBracket => "expected `,` or `}`",
+            ParseErrorKind::DepthLimitExceeded => "nesting depth limit exceeded",
+            ParseErrorKind::DocumentTooLarge => "document size limit exceeded",
+            ParseErrorKind::StringTooLong => "string length limit exceeded",
+            ParseErrorKind::ArrayTooLong => "array length limit exceeded",
+            ParseErrorKind::ObjectTooLong => "object member limit exceeded",
+            ParseErrorKind::TooManyNodes => "value count limit exceeded",
+            ParseErrorKind::DuplicateKey => "duplicate key in object",
+        };
+        f.write_str(msg)
+    }
+}
+
+// 从根节点到出错位置所经过的键或下标
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+    path: Vec<PathSegment>,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, offset: usize, line: usize, column: usize, path: Vec<PathSegment>) -> Self {
+        Self {
+            kind,
+            offset,
+            line,
+            column,
+            path,
+        }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    // 出错位置的字节偏移量
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // 行号，从1开始
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // 列号，从1开始，按字符计数
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    // 形如 `$.a[0].b` 的路径表示
+    pub fn path_string(&self) -> String {
+        let mut s = String::from("$");
+        for segment in &self.path {
+            match segment {
+                PathSegment::Key(key) => {
+                    s.push('.');
+                    s.push_str(key);
+                }
+                PathSegment::Index(i) => {
+                    s.push('[');
+                    s.push_str(&i.to_string());
+                    s.push(']');
+                }
+            }
+        }
+        s
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {} (byte {}), path {}",
+            self.kind,
+            self.line,
+            self.column,
+            self.offset,
+            self.path_string()
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum WriteError {
+    // NonFiniteFloats::Error时遇到NaN或±Infinity
+    NonFiniteFloat,
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::NonFiniteFloat => f.write_str("NaN and Infinity cannot be written as JSON numbers"),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
@@ -0,0 +1,152 @@
+use crate::error::ParseError;
+use crate::number::Number;
+use crate::parser::{Event, Parser};
+use std::ops::ControlFlow;
+
+// 推送式(SAX)解析的回调接口，所有方法默认什么都不做；
+// 任一回调返回ControlFlow::Break即中止解析
+pub trait Handler {
+    fn on_null(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_bool(&mut self, _b: bool) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_number(&mut self, _n: &Number) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_string(&mut self, _s: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_key(&mut self, _key: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_start_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_end_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_start_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_end_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl Parser<'_> {
+    // 将剩余的事件依次推送给handler。整个文档解析完毕返回Continue，被handler中止则返回Break
+    pub fn parse_with<H: Handler + ?Sized>(&mut self, handler: &mut H) -> Result<ControlFlow<()>, ParseError> {
+        while let Some(event) = self.next_event()? {
+            let flow = match event {
+                Event::StartObject => handler.on_start_object(),
+                Event::Key(key) => handler.on_key(key),
+                Event::EndObject => handler.on_end_object(),
+                Event::StartArray => handler.on_start_array(),
+                Event::EndArray => handler.on_end_array(),
+                Event::String(s) => handler.on_string(s),
+                Event::Number(n) => handler.on_number(&n),
+                Event::Bool(b) => handler.on_bool(b),
+                Event::Null => handler.on_null(),
+            };
+            if flow.is_break() {
+                return Ok(flow);
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+    use crate::value::Value;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct Stats {
+        values: usize,
+        sum: f64,
+        keys: BTreeMap<String, usize>,
+    }
+
+    impl Handler for Stats {
+        fn on_null(&mut self) -> ControlFlow<()> {
+            self.values += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_number(&mut self, n: &Number) -> ControlFlow<()> {
+            self.values += 1;
+            self.sum += n.as_f64().unwrap();
+            ControlFlow::Continue(())
+        }
+
+        fn on_key(&mut self, key: &str) -> ControlFlow<()> {
+            *self.keys.entry(key.to_string()).or_default() += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn handler_aggregates() {
+        let json = r#"[{"x": 1, "y": 2.5}, {"x": -4, "z": null}, "skipped", true]"#;
+        let mut stats = Stats::default();
+        let flow = Parser::new(json.as_bytes()).parse_with(&mut stats).unwrap();
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(stats.values, 4);
+        assert_eq!(stats.sum, -0.5);
+        assert_eq!(
+            stats.keys.into_iter().collect::<Vec<_>>(),
+            [("x".to_string(), 2), ("y".to_string(), 1), ("z".to_string(), 1)]
+        );
+
+        // Value上的入口，从字符串或reader解析
+        let mut stats = Stats::default();
+        assert_eq!(
+            Value::parse_with_handler(json, &mut stats).unwrap(),
+            ControlFlow::Continue(())
+        );
+        assert_eq!((stats.values, stats.sum), (4, -0.5));
+        let mut stats = Stats::default();
+        let flow = Value::from_reader_with_handler(json.as_bytes(), &mut stats).unwrap();
+        assert_eq!(
+            (flow, stats.values, stats.keys.len()),
+            (ControlFlow::Continue(()), 4, 3)
+        );
+        let e = Value::from_reader_with_handler("[1] 2".as_bytes(), &mut stats)
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::RootNotSingular);
+    }
+
+    #[test]
+    fn handler_abort_early() {
+        // 找到第一个字符串后立即停止，后面的语法错误不会被解析到
+        struct FindString(Option<String>);
+        impl Handler for FindString {
+            fn on_string(&mut self, s: &str) -> ControlFlow<()> {
+                self.0 = Some(s.to_string());
+                ControlFlow::Break(())
+            }
+        }
+        let mut find = FindString(None);
+        let mut parser = Parser::new(br#"[1, "first", "second", ???"#);
+        assert_eq!(parser.parse_with(&mut find).unwrap(), ControlFlow::Break(()));
+        assert_eq!(find.0.as_deref(), Some("first"));
+
+        // 没有中止时照常报告语法错误
+        let e = Parser::new(b"[1, 2").parse_with(&mut Stats::default()).err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::MissCommaOrSquareBracket);
+    }
+}
@@ -0,0 +1,880 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::options::{DuplicateKeys, ParseOptions};
+use crate::parser::Parser;
+use crate::stack::Stack;
+use crate::value::{Value, ValueBuilder};
+
+// 下一个非空白字节在语法上应当是什么
+#[derive(Clone, Copy)]
+enum Expect {
+    Value,
+    ArrayValueOrEnd,
+    KeyOrEnd,
+    Key,
+    Colon,
+    CommaOrEnd,
+}
+
+// 数字的词法状态，对应JSON数字文法中的各个位置
+#[derive(Clone, Copy)]
+enum NumberPhase {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    Exp,
+    ExpSign,
+    ExpDigits,
+}
+
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    Backslash,
+    Unicode { digits: u8, value: u32 },
+}
+
+// 正在读取、可能跨越多个数据块的记号
+#[derive(Clone, Copy)]
+enum Token {
+    None,
+    String {
+        is_key: bool,
+        escape: Escape,
+        high_surrogate: Option<u32>,
+    },
+    Number(NumberPhase),
+    Literal {
+        literal: &'static [u8],
+        matched: usize,
+    },
+}
+
+// 字符串中尚未读完的多字节UTF-8字符：还需要的后续字节数、下一个字节的合法范围和首字节的位置
+#[derive(Clone, Copy)]
+struct PendingUtf8 {
+    remaining: u8,
+    low: u8,
+    high: u8,
+    start: Position,
+}
+
+#[derive(Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+// 增量解析器：输入可以被切成任意大小的数据块依次喂入，
+// 解析状态（包括字符串、转义序列和数字的中间状态）在数据块之间保留。
+// 输入可以包含多个以空白分隔的JSON值，每个值完整后即被返回，因此与Value::parse有两处不同：
+// 空输入或只有空白时返回空的Vec而不是ExpectValue；"99\n1"这样的输入得到两个值，
+// 而Value::parse在第二个值处报告RootNotSingular。其余错误的种类和位置都与Value::parse相同，
+// 之后的每个值按单独的文档检查，大小和值个数的限制也按每个文档计算，已有值之后出现不能开始新值的字节时同样报告RootNotSingular。
+pub struct IncrementalParser {
+    builder: ValueBuilder,
+    expect: Expect,
+    token: Token,
+    // 当前字符串或数字已读取的字节
+    buffer: Stack<u8>,
+    // 下一个字节的位置
+    position: Position,
+    token_start: Position,
+    escape_start: Position,
+    utf8: Option<PendingUtf8>,
+    // 当前字符串中第一个非法UTF-8字节的位置；与Parser一样到闭合引号处才报告
+    invalid_utf8: Option<Position>,
+    // 上一个字节刚好结束了一个\u转义；与Parser一致，输入在此结束时报告为该转义不完整
+    unicode_end: Option<ParseErrorKind>,
+    completed: Vec<Value>,
+    error: Option<ParseError>,
+    options: ParseOptions,
+    // 当前文档的起始偏移和其中已开始解析的值的个数，每个值完整后重新计算，
+    // 长时间使用的数据流不会因为之前的文档而超出限制；之后的文档从它的第一个字节算起
+    document_start: usize,
+    nodes: usize,
+    // 是否已有完整的根值
+    seen_root: bool,
+}
+
+impl Default for IncrementalParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IncrementalParser {
+    pub fn new() -> Self {
+        let start = Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        };
+        Self {
+            builder: ValueBuilder::new(),
+            expect: Expect::Value,
+            token: Token::None,
+            buffer: Stack::new(),
+            position: start,
+            token_start: start,
+            escape_start: start,
+            utf8: None,
+            invalid_utf8: None,
+            unicode_end: None,
+            completed: Vec::new(),
+            error: None,
+            options: ParseOptions::default(),
+            document_start: 0,
+            nodes: 0,
+            seen_root: false,
+        }
+    }
+
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.builder = ValueBuilder::new().with_duplicate_keys(options.duplicate_keys);
+        self.options = options;
+        self
+    }
+
+    // 喂入一块数据，返回在这块数据中完成的所有值。
+    // 出错后解析器不再可用，之后的调用都返回同一个错误
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Value>, ParseError> {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        }
+        for &b in chunk {
+            let result = if self.position.offset - self.document_start < self.options.max_document_size {
+                self.consume(b)
+            } else {
+                Err(self.error(ParseErrorKind::DocumentTooLarge))
+            };
+            if let Err(e) = result {
+                self.error = Some(e.clone());
+                return Err(e);
+            }
+            self.position.offset += 1;
+            if b == b'\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else if (b & 0xC0) != 0x80 {
+                self.position.column += 1;
+            }
+        }
+        Ok(std::mem::take(&mut self.completed))
+    }
+
+    // 输入结束：完成末尾的数字，并检查没有未闭合的值
+    pub fn finish(mut self) -> Result<Vec<Value>, ParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        match self.token {
+            Token::None => {}
+            Token::Number(phase) if IncrementalParser::is_complete(phase) => self.end_number()?,
+            Token::Number(_) => return Err(self.error(ParseErrorKind::InvalidValue)),
+            Token::String { is_key: true, .. } => return Err(self.error_at(self.token_start, ParseErrorKind::MissKey)),
+            Token::String {
+                escape: Escape::Backslash | Escape::Unicode { .. },
+                high_surrogate: Some(_),
+                ..
+            } => return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeSurrogate)),
+            Token::String {
+                escape: Escape::Unicode { .. },
+                ..
+            } => return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeHex)),
+            Token::String {
+                escape: Escape::Backslash,
+                ..
+            } => return Err(self.error_at(self.escape_start, ParseErrorKind::MissQuotationMark)),
+            Token::String { .. } => {
+                return Err(match self.unicode_end {
+                    Some(kind) => self.error_at(self.escape_start, kind),
+                    None => self.error(ParseErrorKind::MissQuotationMark),
+                });
+            }
+            Token::Literal { .. } => return Err(self.error_at(self.token_start, ParseErrorKind::InvalidValue)),
+        }
+        if self.builder.depth() > 0 {
+            let kind = match self.expect {
+                Expect::Value | Expect::ArrayValueOrEnd => ParseErrorKind::ExpectValue,
+                Expect::KeyOrEnd | Expect::Key => ParseErrorKind::MissKey,
+                Expect::Colon => ParseErrorKind::MissColon,
+                Expect::CommaOrEnd if self.builder.in_object() => ParseErrorKind::MissCommaOrCurlyBracket,
+                Expect::CommaOrEnd => ParseErrorKind::MissCommaOrSquareBracket,
+            };
+            return Err(self.error(kind));
+        }
+        Ok(self.completed)
+    }
+
+    fn consume(&mut self, b: u8) -> Result<(), ParseError> {
+        match self.token {
+            Token::None => {}
+            Token::String { is_key, .. } => {
+                let result = self.consume_string(b);
+                // 与Parser一致，键本身的语法错误都报告为缺少键
+                return match result {
+                    Err(e)
+                        if is_key
+                            && !matches!(e.kind(), ParseErrorKind::StringTooLong | ParseErrorKind::DuplicateKey) =>
+                    {
+                        Err(self.error_at(self.token_start, ParseErrorKind::MissKey))
+                    }
+                    result => result,
+                };
+            }
+            Token::Literal { literal, matched } => {
+                if b != literal[matched] {
+                    return Err(self.error_at(self.token_start, ParseErrorKind::InvalidValue));
+                }
+                if matched + 1 < literal.len() {
+                    self.token = Token::Literal {
+                        literal,
+                        matched: matched + 1,
+                    };
+                    return Ok(());
+                }
+                self.token = Token::None;
+                let value = match literal[0] {
+                    b'n' => Value::Null,
+                    c => Value::Bool(c == b't'),
+                };
+                self.end_value(value);
+                return Ok(());
+            }
+            Token::Number(phase) => {
+                if let Some(next) = IncrementalParser::next_number_phase(phase, b) {
+                    self.buffer.push_byte(b);
+                    self.token = Token::Number(next);
+                    return Ok(());
+                }
+                match phase {
+                    NumberPhase::Zero if b.is_ascii_digit() => {
+                        return Err(self.error(ParseErrorKind::RootNotSingular));
+                    }
+                    phase if !IncrementalParser::is_complete(phase) => {
+                        return Err(self.error(ParseErrorKind::InvalidValue));
+                    }
+                    _ => self.end_number()?,
+                }
+                // 数字在遇到第一个不属于它的字节时才结束，这个字节继续按语法处理
+            }
+        }
+
+        if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+            return Ok(());
+        }
+        match self.expect {
+            Expect::ArrayValueOrEnd if b == b']' => self.end_container(),
+            Expect::Value | Expect::ArrayValueOrEnd => return self.start_value(b),
+            Expect::KeyOrEnd if b == b'}' => self.end_container(),
+            Expect::KeyOrEnd | Expect::Key => {
+                if self.builder.len() >= self.options.max_object_length {
+                    return Err(self.error(ParseErrorKind::ObjectTooLong));
+                }
+                if b != b'"' {
+                    return Err(self.error(ParseErrorKind::MissKey));
+                }
+                self.start_string(true);
+            }
+            Expect::Colon => {
+                if b != b':' {
+                    return Err(self.error(ParseErrorKind::MissColon));
+                }
+                self.expect = Expect::Value;
+            }
+            Expect::CommaOrEnd => match (self.builder.in_object(), b) {
+                (false, b',') => self.expect = Expect::Value,
+                (false, b']') | (true, b'}') => self.end_container(),
+                (false, _) => return Err(self.error(ParseErrorKind::MissCommaOrSquareBracket)),
+                (true, b',') => self.expect = Expect::Key,
+                (true, _) => return Err(self.error(ParseErrorKind::MissCommaOrCurlyBracket)),
+            },
+        }
+        Ok(())
+    }
+
+    fn start_value(&mut self, b: u8) -> Result<(), ParseError> {
+        // 与Value::parse一致，已有完整的值之后出现不能开始新值的字节时报告根节点不唯一
+        let starts_value = matches!(b, b'"' | b'[' | b'{' | b'n' | b't' | b'f' | b'-' | b'0'..=b'9');
+        if !starts_value && self.builder.depth() == 0 && self.seen_root {
+            return Err(self.error(ParseErrorKind::RootNotSingular));
+        }
+        self.token_start = self.position;
+        if self.builder.depth() == 0 && self.seen_root {
+            self.document_start = self.position.offset;
+        }
+        if matches!(b, b'[' | b'{') && self.builder.depth() >= self.options.max_depth {
+            return Err(self.error(ParseErrorKind::DepthLimitExceeded));
+        }
+        if self.builder.depth() > 0 && !self.builder.in_object() && self.builder.len() >= self.options.max_array_length
+        {
+            return Err(self.error(ParseErrorKind::ArrayTooLong));
+        }
+        if self.nodes >= self.options.max_nodes {
+            return Err(self.error(ParseErrorKind::TooManyNodes));
+        }
+        self.nodes += 1;
+        match b {
+            b'"' => self.start_string(false),
+            b'[' => {
+                self.builder.start_array();
+                self.expect = Expect::ArrayValueOrEnd;
+            }
+            b'{' => {
+                self.builder.start_object();
+                self.expect = Expect::KeyOrEnd;
+            }
+            b'n' | b't' | b'f' => {
+                let literal: &'static [u8] = match b {
+                    b'n' => b"null",
+                    b't' => b"true",
+                    _ => b"false",
+                };
+                self.token = Token::Literal { literal, matched: 1 };
+            }
+            b'-' | b'0'..=b'9' => {
+                let phase = match b {
+                    b'-' => NumberPhase::Minus,
+                    b'0' => NumberPhase::Zero,
+                    _ => NumberPhase::Int,
+                };
+                self.buffer.push_byte(b);
+                self.token = Token::Number(phase);
+            }
+            _ => return Err(self.error(ParseErrorKind::InvalidValue)),
+        }
+        Ok(())
+    }
+
+    fn start_string(&mut self, is_key: bool) {
+        self.token_start = self.position;
+        self.invalid_utf8 = None;
+        self.token = Token::String {
+            is_key,
+            escape: Escape::None,
+            high_surrogate: None,
+        };
+    }
+
+    fn consume_string(&mut self, b: u8) -> Result<(), ParseError> {
+        let Token::String {
+            is_key,
+            escape,
+            mut high_surrogate,
+        } = self.token
+        else {
+            return Ok(());
+        };
+        self.unicode_end = None;
+        if self.buffer.len() > self.options.max_string_length {
+            return Err(self.error_at(self.token_start, ParseErrorKind::StringTooLong));
+        }
+        // 多字节字符被引号、反斜杠或其他不合法的字节打断时，非法字节从它的首字节开始
+        if let Some(pending) = self.utf8
+            && !(pending.low..=pending.high).contains(&b)
+        {
+            self.utf8 = None;
+            self.invalid_utf8.get_or_insert(pending.start);
+        }
+        let escape = match escape {
+            // 高代理项之后必须紧跟`\u`开头的低代理项
+            Escape::None if high_surrogate.is_some() && b != b'\\' => {
+                return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeSurrogate));
+            }
+            Escape::Backslash if high_surrogate.is_some() && b != b'u' => {
+                return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeSurrogate));
+            }
+            Escape::None if b >= 0x80 => {
+                self.consume_utf8(b);
+                Escape::None
+            }
+            Escape::None => match b {
+                b'"' => return self.end_string(is_key),
+                b'\\' => {
+                    if high_surrogate.is_none() {
+                        self.escape_start = self.position;
+                    }
+                    Escape::Backslash
+                }
+                b if b < 0x20 => return Err(self.error(ParseErrorKind::InvalidStringChar)),
+                b => {
+                    self.buffer.push_byte(b);
+                    Escape::None
+                }
+            },
+            Escape::Backslash if b == b'u' => Escape::Unicode { digits: 0, value: 0 },
+            Escape::Backslash => match Parser::unescape(b) {
+                Some(c) => {
+                    self.buffer.push_byte(c);
+                    Escape::None
+                }
+                None => return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidStringEscape)),
+            },
+            Escape::Unicode { digits, value } => {
+                let Some(digit) = Parser::hex_digit(b) else {
+                    return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeHex));
+                };
+                let value = (value << 4) | digit;
+                if digits < 3 {
+                    Escape::Unicode {
+                        digits: digits + 1,
+                        value,
+                    }
+                } else {
+                    self.unicode_end = Some(match high_surrogate {
+                        Some(_) => ParseErrorKind::InvalidUnicodeSurrogate,
+                        None => ParseErrorKind::InvalidUnicodeHex,
+                    });
+                    let code = match high_surrogate.take() {
+                        Some(high) if (0xDC00..=0xDFFF).contains(&value) => {
+                            Some(0x10000 + (high - 0xD800) * 0x400 + (value - 0xDC00))
+                        }
+                        Some(_) => None,
+                        None if (0xD800..=0xDBFF).contains(&value) => {
+                            high_surrogate = Some(value);
+                            Some(value)
+                        }
+                        None => Some(value),
+                    };
+                    let encoded = match code {
+                        Some(_) if high_surrogate.is_some() => Some(()),
+                        Some(c) => Parser::encode_utf8(&mut self.buffer, c),
+                        None => None,
+                    };
+                    if encoded.is_none() {
+                        return Err(self.error_at(self.escape_start, ParseErrorKind::InvalidUnicodeSurrogate));
+                    }
+                    Escape::None
+                }
+            }
+        };
+        self.token = Token::String {
+            is_key,
+            escape,
+            high_surrogate,
+        };
+        Ok(())
+    }
+
+    // 逐字节校验多字节UTF-8字符，只记录第一个非法字节的位置，照常读到字符串结束
+    fn consume_utf8(&mut self, b: u8) {
+        self.buffer.push_byte(b);
+        let Some(pending) = self.utf8.take() else {
+            // 首字节决定后续字节数；排除过长编码、代理项和超出U+10FFFF的码点
+            let (remaining, low, high) = match b {
+                0xC2..=0xDF => (1, 0x80, 0xBF),
+                0xE0 => (2, 0xA0, 0xBF),
+                0xED => (2, 0x80, 0x9F),
+                0xE1..=0xEF => (2, 0x80, 0xBF),
+                0xF0 => (3, 0x90, 0xBF),
+                0xF4 => (3, 0x80, 0x8F),
+                0xF1..=0xF3 => (3, 0x80, 0xBF),
+                _ => {
+                    self.invalid_utf8.get_or_insert(self.position);
+                    return;
+                }
+            };
+            self.utf8 = Some(PendingUtf8 {
+                remaining,
+                low,
+                high,
+                start: self.position,
+            });
+            return;
+        };
+        if pending.remaining > 1 {
+            self.utf8 = Some(PendingUtf8 {
+                remaining: pending.remaining - 1,
+                low: 0x80,
+                high: 0xBF,
+                ..pending
+            });
+        }
+    }
+
+    fn end_string(&mut self, is_key: bool) -> Result<(), ParseError> {
+        self.token = Token::None;
+        if let Some(position) = self.invalid_utf8 {
+            return Err(self.error_at(position, ParseErrorKind::InvalidUtf8));
+        }
+        let bytes = self.buffer.pop_bytes(self.buffer.len());
+        let s = match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(_) => return Err(self.error_at(self.token_start, ParseErrorKind::InvalidUtf8)),
+        };
+        if is_key {
+            if self.options.duplicate_keys == DuplicateKeys::Error && self.builder.contains_key(&s) {
+                return Err(self.error_at(self.token_start, ParseErrorKind::DuplicateKey));
+            }
+            self.builder.key(s);
+            self.expect = Expect::Colon;
+        } else {
+            self.end_value(Value::String(s));
+        }
+        Ok(())
+    }
+
+    fn end_number(&mut self) -> Result<(), ParseError> {
+        self.token = Token::None;
+        let bytes = self.buffer.pop_bytes(self.buffer.len());
+        let is_float = bytes.iter().any(|&b| matches!(b, b'.' | b'e' | b'E'));
+        match Parser::convert_number(&bytes, is_float) {
+            Some(n) => {
+                self.end_value(Value::Number(n));
+                Ok(())
+            }
+            None => Err(self.error_at(self.token_start, ParseErrorKind::NumberTooBig)),
+        }
+    }
+
+    fn end_container(&mut self) {
+        match self.builder.end() {
+            Some(root) => self.end_root(root),
+            None => self.expect = Expect::CommaOrEnd,
+        }
+    }
+
+    fn end_value(&mut self, value: Value) {
+        match self.builder.value(value) {
+            Some(root) => self.end_root(root),
+            None => self.expect = Expect::CommaOrEnd,
+        }
+    }
+
+    // 一个根值完整，之后的输入按新的文档计算大小和值的个数
+    fn end_root(&mut self, root: Value) {
+        self.completed.push(root);
+        self.expect = Expect::Value;
+        self.document_start = self.position.offset + 1;
+        self.nodes = 0;
+        self.seen_root = true;
+    }
+
+    fn next_number_phase(phase: NumberPhase, b: u8) -> Option<NumberPhase> {
+        let next = match (phase, b) {
+            (NumberPhase::Minus, b'0') => NumberPhase::Zero,
+            (NumberPhase::Minus, b'1'..=b'9') => NumberPhase::Int,
+            (NumberPhase::Int, b'0'..=b'9') => NumberPhase::Int,
+            (NumberPhase::Zero | NumberPhase::Int, b'.') => NumberPhase::Dot,
+            (NumberPhase::Dot | NumberPhase::Frac, b'0'..=b'9') => NumberPhase::Frac,
+            (NumberPhase::Zero | NumberPhase::Int | NumberPhase::Frac, b'e' | b'E') => NumberPhase::Exp,
+            (NumberPhase::Exp, b'+' | b'-') => NumberPhase::ExpSign,
+            (NumberPhase::Exp | NumberPhase::ExpSign | NumberPhase::ExpDigits, b'0'..=b'9') => NumberPhase::ExpDigits,
+            _ => return None,
+        };
+        Some(next)
+    }
+
+    fn is_complete(phase: NumberPhase) -> bool {
+        matches!(
+            phase,
+            NumberPhase::Zero | NumberPhase::Int | NumberPhase::Frac | NumberPhase::ExpDigits
+        )
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        self.error_at(self.position, kind)
+    }
+
+    fn error_at(&self, position: Position, kind: ParseErrorKind) -> ParseError {
+        // 正在解析容器中的某个值时，路径包含该值所在的下标或键
+        let in_value = match self.token {
+            Token::String { is_key, .. } => !is_key,
+            Token::None => matches!(self.expect, Expect::Value | Expect::ArrayValueOrEnd),
+            _ => true,
+        };
+        let path = self.builder.path(in_value);
+        ParseError::new(kind, position.offset, position.line, position.column, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按固定大小切块喂入整个输入
+    fn parse_chunked(json: &[u8], chunk_size: usize) -> Result<Vec<Value>, ParseError> {
+        let mut parser = IncrementalParser::new();
+        let mut values = Vec::new();
+        for chunk in json.chunks(chunk_size) {
+            values.extend(parser.feed(chunk)?);
+        }
+        values.extend(parser.finish()?);
+        Ok(values)
+    }
+
+    #[test]
+    fn incremental_matches_parse() {
+        let inputs = [
+            "null",
+            " true ",
+            "-0.125e+2",
+            "12345678901234567890",
+            r#""a\"b\\c\/\né𝄞 中文""#,
+            r#"{"a": [1, 2, {"b": null}], "c": {"d": [], "e": {}}, "f": "x"}"#,
+            "[[[[]]], [1.5, -2, 0], \"\"]",
+        ];
+        for json in inputs {
+            let expected = Value::parse(json).unwrap();
+            for chunk_size in 1..=json.len() {
+                assert_eq!(
+                    parse_chunked(json.as_bytes(), chunk_size).unwrap(),
+                    std::slice::from_ref(&expected),
+                    "{json}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_errors_match_parse() {
+        let inputs = [
+            "[1, 2",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1 "b": 2}"#,
+            "{1: 2}",
+            "[tru]",
+            "[nul",
+            "[1.]",
+            "-",
+            "[01]",
+            "1e309",
+            r#"["\q"]"#,
+            r#"["\u12G4"]"#,
+            r#"["\uD800x"]"#,
+            r#"["\uD800A"]"#,
+            "[\"a\x01\"]",
+            r#"{"a": ["x", "#,
+            r#"["abc"#,
+        ];
+        for json in inputs {
+            // 启用arbitrary_precision时任意大的数字都合法
+            if cfg!(feature = "arbitrary_precision") && json == "1e309" {
+                continue;
+            }
+            let expected = Value::parse(json).err().unwrap();
+            for chunk_size in 1..=json.len() {
+                let e = parse_chunked(json.as_bytes(), chunk_size).err().unwrap();
+                assert_eq!(e, expected, "{json} in chunks of {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_invalid_utf8() {
+        let inputs: [&[u8]; 5] = [
+            b"[\"ab\xff\"]",
+            b"[\"\xc3\"]",
+            b"[\"\xe0\x80\x80\"]",
+            b"[\"\xf4\x90\x80\x80\"]",
+            b"{\"\xe4\xbd\": 1}",
+        ];
+        for json in inputs {
+            let expected = Value::parse_slice(json).err().unwrap();
+            for chunk_size in 1..=json.len() {
+                assert_eq!(parse_chunked(json, chunk_size).err().unwrap(), expected);
+            }
+        }
+        assert_eq!(
+            parse_chunked("\"é𝄞\"".as_bytes(), 1).unwrap(),
+            [Value::String("é𝄞".to_string())]
+        );
+    }
+
+    // 与Value::parse逐个比较错误：整块喂入和逐字节喂入都应当得到相同的错误种类和位置
+    #[test]
+    fn incremental_differential() {
+        let inputs: &[&[u8]] = &[
+            b"\"\xff\\u12",
+            b"\"\xff\\u12\"",
+            b"\"\xff\\q\"",
+            b"\"\xff\x01\"",
+            b"\"\xff",
+            b"\"\xe4\"",
+            b"\"\xe4\\n\"",
+            b"\"\xe4a\xff\"",
+            b"{\"\xff\": 1}",
+            b"\"\\u0041",
+            b"\"\\uD800",
+            b"\"\\uD800\\u",
+            b"\"\\uD800\\uDC00",
+            b"\"\\uD800\\u12",
+            b"\"\\uD800\\",
+            b"\"\\uD800x\"",
+            b"{\"\xe4\\x\": 1}",
+            b"\"\\",
+            b"\"a",
+            b"1x",
+            b"1 x",
+            b"[1] }",
+            b"[1]]",
+            b"{} ,",
+            b"01",
+            b"-x",
+            b"-",
+            b"1.",
+            b"1e",
+            b"1e+",
+            b"tru",
+            b"trux",
+            b"nul l",
+            b"[",
+            b"{\"a\"",
+            b"{\"a\":",
+            b"x",
+            b"]",
+            b"[1,]",
+            b"{\"a\": 1,}",
+        ];
+        for &json in inputs {
+            let expected = Value::parse_slice(json).err().unwrap();
+            for chunk_size in 1..=json.len() {
+                let e = parse_chunked(json, chunk_size).err().unwrap();
+                assert_eq!(
+                    (e.kind(), e.offset()),
+                    (expected.kind(), expected.offset()),
+                    "{} in chunks of {chunk_size}",
+                    String::from_utf8_lossy(json)
+                );
+                assert_eq!(e, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_limits() {
+        let limits = ParseOptions {
+            max_document_size: 24,
+            max_string_length: 4,
+            max_array_length: 3,
+            max_object_length: 2,
+            max_nodes: 8,
+            ..Default::default()
+        };
+        let inputs = [
+            r#"[1, "abcd", {"a": []}]"#,
+            r#"[1,                   2]"#,
+            r#"["abcde"]"#,
+            r#"{"abcde": 1}"#,
+            r#"[1, 2, 3, 4]"#,
+            r#"{"a": 1, "b": 2, "c": 3}"#,
+            r#"[[1, 2], [3, 4], [5]]"#,
+        ];
+        for json in inputs {
+            let expected = Value::parse_with_options(json, limits.clone()).map(|v| vec![v]);
+            for chunk_size in 1..=json.len() {
+                let mut parser = IncrementalParser::new().with_options(limits.clone());
+                let values = json
+                    .as_bytes()
+                    .chunks(chunk_size)
+                    .try_fold(Vec::new(), |mut values, chunk| {
+                        values.extend(parser.feed(chunk)?);
+                        Ok(values)
+                    })
+                    .and_then(|mut values| {
+                        values.extend(parser.finish()?);
+                        Ok(values)
+                    });
+                assert_eq!(values, expected, "{json} in chunks of {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_limits_per_document() {
+        let mut parser = IncrementalParser::new().with_options(ParseOptions {
+            max_document_size: 6,
+            max_nodes: 3,
+            ..Default::default()
+        });
+        for _ in 0..100 {
+            assert_eq!(parser.feed(b"[1, 2]\n").unwrap(), [Value::parse("[1, 2]").unwrap()]);
+        }
+        let e = parser.feed(b"[1,2,3]").err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::TooManyNodes, 705));
+
+        let mut parser = IncrementalParser::new().with_options(ParseOptions {
+            max_document_size: 6,
+            ..Default::default()
+        });
+        assert_eq!(parser.feed(b"[1, 2] ").unwrap().len(), 1);
+        let e = parser.feed(b"[1,  2]").err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::DocumentTooLarge, 13));
+    }
+
+    #[test]
+    fn incremental_duplicate_keys() {
+        let json = r#"{"a": [1], "b": {"c": 1, "c": 2}, "a": 2, "a": {}}"#;
+        for policy in [
+            DuplicateKeys::Error,
+            DuplicateKeys::FirstWins,
+            DuplicateKeys::LastWins,
+            DuplicateKeys::CollectAll,
+        ] {
+            let options = ParseOptions {
+                duplicate_keys: policy,
+                ..Default::default()
+            };
+            let expected = Value::parse_with_options(json, options.clone());
+            let mut parser = IncrementalParser::new().with_options(options);
+            let values = parser.feed(json.as_bytes()).and_then(|mut values| {
+                values.extend(parser.finish()?);
+                Ok(values)
+            });
+            assert_eq!(values, expected.map(|v| vec![v]));
+        }
+    }
+
+    #[test]
+    fn incremental_split_tokens() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(br#"["\u"#).unwrap(), []);
+        assert_eq!(parser.feed(b"D8").unwrap(), []);
+        assert_eq!(parser.feed(br#"34\uDD1"#).unwrap(), []);
+        assert_eq!(parser.feed(br#"E", 12"#).unwrap(), []);
+        assert_eq!(parser.feed(b"3.4").unwrap(), []);
+        assert_eq!(
+            parser.feed(b"e1]").unwrap(),
+            [Value::parse(r#"["𝄞", 123.4e1]"#).unwrap()]
+        );
+        assert_eq!(parser.finish().unwrap(), []);
+    }
+
+    #[test]
+    fn incremental_value_stream() {
+        let mut parser = IncrementalParser::new();
+        let values = parser.feed(b"{\"id\": 1}\n{\"id\": 2}\n[").unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(parser.feed(b"]\n4").unwrap(), [Value::Array(vec![])]);
+        // 末尾的数字要等到输入结束才能确定已经完整
+        assert_eq!(parser.finish().unwrap(), [Value::parse("4").unwrap()]);
+
+        assert_eq!(IncrementalParser::new().finish().unwrap(), []);
+        assert_eq!(parse_chunked(b" \n", 1).unwrap(), []);
+        assert_eq!(
+            parse_chunked(b"99\n1", 1).unwrap(),
+            [Value::parse("99").unwrap(), Value::parse("1").unwrap()]
+        );
+        let e = parse_chunked(b"[1] ]", 2).err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::RootNotSingular, 4));
+
+        // 出错后继续喂入数据仍然返回同一个错误
+        let mut parser = IncrementalParser::new();
+        let e = parser.feed(b"[1,\n ?").err().unwrap();
+        assert_eq!((e.kind(), e.line(), e.column()), (ParseErrorKind::InvalidValue, 2, 2));
+        assert_eq!(e.path_string(), "$[1]");
+        assert_eq!(parser.feed(b"2]").err().unwrap(), e);
+
+        let mut parser = IncrementalParser::new().with_options(ParseOptions {
+            max_depth: 1,
+            ..Default::default()
+        });
+        assert_eq!(parser.feed(b"[] [").unwrap(), [Value::Array(vec![])]);
+        let e = parser.feed(b"[").err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::DepthLimitExceeded, 4));
+    }
+}
@@ -0,0 +1,273 @@
+use crate::dict::Dict;
+use crate::error::{ParseError, ParseErrorKind, WriteError};
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParseOptions, WriteOptions};
+use crate::parser::Parser;
+use crate::value::{Events, Node, NodeRef, Value, build_from_parser, drop_tree, tree_eq};
+use std::collections::HashSet;
+use std::io::Read;
+use std::ops::Index;
+use std::sync::Arc;
+
+// InternedValue::Object使用的对象类型，后端与Map相同。驻留只节省键的内存，
+// 查找和比较仍按字符串内容进行，与Map的开销相同
+pub type InternedMap = Dict<Arc<str>, InternedValue>;
+
+// 键的驻留池，可在多次解析之间共用：相同的键共享同一个Arc<str>，只在第一次出现时分配内存。
+// 对于都已驻留的两个键，调用方可以用Arc::ptr_eq代替比较内容；池满之后新出现的键不共享，
+// 指针不同并不说明内容不同
+#[derive(Debug, Clone)]
+pub struct KeyInterner {
+    keys: HashSet<Arc<str>>,
+    capacity: usize,
+}
+
+impl Default for KeyInterner {
+    fn default() -> Self {
+        KeyInterner::with_capacity(4096)
+    }
+}
+
+impl KeyInterner {
+    pub fn new() -> Self {
+        KeyInterner::default()
+    }
+
+    // 最多驻留capacity个不同的键；池满之后新出现的键照常分配，不再加入池中
+    pub fn with_capacity(capacity: usize) -> Self {
+        KeyInterner {
+            keys: HashSet::new(),
+            capacity,
+        }
+    }
+
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        if let Some(interned) = self.keys.get(key) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(key);
+        if self.keys.len() < self.capacity {
+            self.keys.insert(interned.clone());
+        }
+        interned
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // 清空池；已解析的文档仍持有各自的键
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
+
+// 对象的键由KeyInterner驻留的Value，其余与Value相同
+#[derive(Debug)]
+pub enum InternedValue {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<InternedValue>),
+    Object(InternedMap),
+}
+
+impl Index<&str> for InternedValue {
+    type Output = InternedValue;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        if let InternedValue::Object(dict) = self {
+            &dict[key]
+        } else {
+            panic!("Only InternedValue::Object() can be accessed by key")
+        }
+    }
+}
+
+impl Index<usize> for InternedValue {
+    type Output = InternedValue;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if let InternedValue::Array(arr) = self {
+            &arr[index]
+        } else {
+            panic!("Only InternedValue::Array() can be accessed by index")
+        }
+    }
+}
+
+impl std::fmt::Display for InternedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match Value::stringify_events(Events::for_output(self), &WriteOptions::default()) {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl PartialEq for InternedValue {
+    fn eq(&self, other: &Self) -> bool {
+        tree_eq(self, other)
+    }
+}
+
+impl Eq for InternedValue {}
+
+impl Node for InternedValue {
+    type Key = Arc<str>;
+
+    fn from_array(arr: Vec<Self>) -> Self {
+        InternedValue::Array(arr)
+    }
+
+    fn from_object(object: InternedMap) -> Self {
+        InternedValue::Object(object)
+    }
+
+    fn from_scalar(scalar: NodeRef<'_, Self>) -> Self {
+        match scalar {
+            NodeRef::Bool(b) => InternedValue::Bool(b),
+            NodeRef::Number(n) => InternedValue::Number(n.clone()),
+            _ => InternedValue::Null,
+        }
+    }
+
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match self {
+            InternedValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    fn as_node(&self) -> NodeRef<'_, Self> {
+        match self {
+            InternedValue::Null => NodeRef::Null,
+            InternedValue::Bool(b) => NodeRef::Bool(*b),
+            InternedValue::Number(n) => NodeRef::Number(n),
+            InternedValue::String(s) => NodeRef::String(s),
+            InternedValue::Array(arr) => NodeRef::Array(arr),
+            InternedValue::Object(object) => NodeRef::Object(object),
+        }
+    }
+
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            InternedValue::Array(arr) => out.append(arr),
+            InternedValue::Object(object) => out.extend(std::mem::take(object).into_iter().map(|(_, v)| v)),
+            _ => {}
+        }
+    }
+}
+
+impl InternedValue {
+    // 与Value::drop_deep相同，不使用递归地释放这个值
+    pub fn drop_deep(mut self) {
+        drop_tree(&mut self);
+    }
+
+    pub fn parse(json: &str, interner: &mut KeyInterner) -> Result<InternedValue, ParseError> {
+        InternedValue::parse_slice(json.as_bytes(), interner)
+    }
+
+    pub fn parse_slice(json: &[u8], interner: &mut KeyInterner) -> Result<InternedValue, ParseError> {
+        InternedValue::parse_document(Parser::new(json), interner)
+    }
+
+    pub fn parse_with_options(
+        json: &str,
+        options: ParseOptions,
+        interner: &mut KeyInterner,
+    ) -> Result<InternedValue, ParseError> {
+        InternedValue::parse_document(Parser::new(json.as_bytes()).with_options(options), interner)
+    }
+
+    pub fn from_reader<R: Read>(reader: R, interner: &mut KeyInterner) -> Result<InternedValue, ParseError> {
+        InternedValue::parse_document(Parser::from_reader(reader), interner)
+    }
+
+    fn parse_document(mut parser: Parser, interner: &mut KeyInterner) -> Result<InternedValue, ParseError> {
+        let v = InternedValue::from_parser(&mut parser, interner)?;
+        match parser.next_event()? {
+            None => Ok(v),
+            Some(_) => Err(parser.error(ParseErrorKind::RootNotSingular)),
+        }
+    }
+
+    // 从事件流中读取下一个完整的值
+    pub fn from_parser(parser: &mut Parser, interner: &mut KeyInterner) -> Result<InternedValue, ParseError> {
+        build_from_parser(
+            parser,
+            |parser| interner.intern(parser.current()),
+            |parser| InternedValue::String(parser.owned_string()),
+        )
+    }
+
+    // 复制为键不再共享的Value
+    pub fn into_owned(self) -> Value {
+        let owned = Value::from_events(Events::new(&self), DuplicateKeys::LastWins);
+        self.drop_deep();
+        owned
+    }
+
+    pub fn to_string_with_options(&self, options: WriteOptions) -> Result<String, WriteError> {
+        Value::stringify_events(Events::for_output(self), &options)
+    }
+}
+
+impl From<InternedValue> for Value {
+    fn from(value: InternedValue) -> Self {
+        value.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(v: &InternedValue) -> Vec<Arc<str>> {
+        match v {
+            InternedValue::Object(object) => object.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn interned_keys_are_shared() {
+        let mut interner = KeyInterner::new();
+        let json = r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b\n"}]"#;
+        let v = InternedValue::parse(json, &mut interner).unwrap();
+        let w = InternedValue::parse(r#"{"name": "c", "id": 3}"#, &mut interner).unwrap();
+        assert_eq!(interner.len(), 2);
+        let id = interner.intern("id");
+        for object in [&v[0], &v[1], &w] {
+            let key = keys(object).into_iter().find(|k| &**k == "id").unwrap();
+            assert!(Arc::ptr_eq(&key, &id));
+        }
+        assert_eq!(v.to_string(), Value::parse(json).unwrap().to_string());
+        assert_eq!(v.into_owned(), Value::parse(json).unwrap());
+        assert_eq!(Value::from(w), Value::parse(r#"{"id": 3, "name": "c"}"#).unwrap());
+    }
+
+    #[test]
+    fn interner_capacity() {
+        let mut interner = KeyInterner::with_capacity(2);
+        let v = InternedValue::parse(r#"{"a": 1, "c": 2, "d": 3}"#, &mut interner).unwrap();
+        assert_eq!((interner.len(), interner.capacity()), (2, 2));
+        // 池满之后的键照常解析，只是不再共享
+        assert_eq!(v["d"], InternedValue::Number(Number::from(3)));
+        assert!(!Arc::ptr_eq(&interner.intern("d"), &interner.intern("d")));
+        assert!(Arc::ptr_eq(&interner.intern("a"), &interner.intern("a")));
+        interner.clear();
+        assert!(interner.is_empty());
+        assert_eq!(v["a"], InternedValue::Number(Number::from(1)));
+    }
+}
@@ -1,14 +1,154 @@
+mod borrowed;
 mod context;
+mod diagnostic;
 mod dict;
 mod error;
+mod handler;
+mod incremental;
+mod interned;
 mod number;
+mod options;
+mod parser;
+mod raw;
 mod stack;
+mod tape;
 mod value;
 
-pub use crate::error::ParseError;
-pub use crate::value::Value;
+pub use crate::borrowed::{BorrowedMap, BorrowedValue};
+pub use crate::diagnostic::Diagnostic;
+pub use crate::dict::{Backend, Dict, Entry, Key, OccupiedEntry, VacantEntry, VecMap};
+pub use crate::error::{ParseError, ParseErrorKind, PathSegment, WriteError};
+pub use crate::handler::Handler;
+pub use crate::incremental::IncrementalParser;
+pub use crate::interned::{InternedMap, InternedValue, KeyInterner};
+pub use crate::number::Number;
+pub use crate::options::{DuplicateKeys, NonFiniteFloats, ParseOptions, WriteOptions};
+pub use crate::parser::{Event, Parser};
+pub use crate::raw::RawValue;
+pub use crate::tape::{Tape, ValueRef};
+pub use crate::value::{Map, Value};
 
+#[cfg(feature = "arena")]
+mod arena;
 #[cfg(feature = "serde")]
 mod serde_support;
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(feature = "arena")]
+pub use crate::arena::{Arena, ArenaMap, ArenaValue};
 #[cfg(feature = "serde")]
 pub use serde_support::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 嵌套depth层的对象和数组，用来检查各种表示都不使用递归
+    pub(crate) fn deep_document(depth: usize) -> String {
+        "[{\"a\":".repeat(depth) + "\"s\"" + &"}]".repeat(depth)
+    }
+
+    // 用一种表示解析json，返回它输出的文本和转换得到的Value；
+    // 能比较的表示再解析一次检查相等，嵌套极深的值用drop_deep释放
+    type Parse = fn(&str, ParseOptions) -> Result<(String, Value), ParseError>;
+
+    fn representations() -> Vec<(&'static str, Parse)> {
+        vec![
+            ("Value", |json, options| {
+                let v = Value::parse_with_options(json, options.clone())?;
+                let again = Value::parse_with_options(json, options)?;
+                assert!(v == again);
+                again.drop_deep();
+                Ok((v.to_string(), v))
+            }),
+            ("BorrowedValue", |json, options| {
+                let v = BorrowedValue::parse_with_options(json, options.clone())?;
+                let again = BorrowedValue::parse_with_options(json, options)?;
+                assert!(v == again);
+                again.drop_deep();
+                Ok((v.to_string(), v.into_owned()))
+            }),
+            ("InternedValue", |json, options| {
+                let mut interner = KeyInterner::new();
+                let v = InternedValue::parse_with_options(json, options.clone(), &mut interner)?;
+                let again = InternedValue::parse_with_options(json, options, &mut interner)?;
+                assert!(v == again);
+                again.drop_deep();
+                Ok((v.to_string(), v.into_owned()))
+            }),
+            ("Tape", |json, options| {
+                let tape = Tape::parse_with_options(json, options)?;
+                Ok((tape.to_string(), tape.to_owned()))
+            }),
+            ("RawValue", |json, options| {
+                let raw = RawValue::parse_with_options(json, options)?;
+                Ok((raw.to_string(), raw.to_value()))
+            }),
+            #[cfg(feature = "arena")]
+            ("ArenaValue", |json, options| {
+                let arena = Arena::new();
+                let v = ArenaValue::parse_with_options(json, options.clone(), &arena)?;
+                assert!(v == ArenaValue::parse_with_options(json, options, &arena)?);
+                Ok((v.to_string(), v.to_owned()))
+            }),
+        ]
+    }
+
+    #[test]
+    fn errors_match_value() {
+        let inputs = [
+            "",
+            "  ",
+            "tru",
+            "[1, 2",
+            r#"{"a" 1}"#,
+            r#""abc"#,
+            r#"["\x"]"#,
+            "\"a\u{1}\"",
+            r#"{"a": 1, "a": 2}"#,
+            "[1] 2",
+            r#"{"a": [1, }"#,
+            r#"{"a": {"b": [}}"#,
+            "[[[1]]]",
+            r#""12345""#,
+            r#"{"12345": 1}"#,
+        ];
+        let options = || ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_string_length: 4,
+            max_depth: 2,
+            ..Default::default()
+        };
+        // RawValue只验证文本，保留其中重复的键
+        let duplicates = r#"{"a": 1, "a": 2}"#;
+        for (name, parse) in representations() {
+            for json in inputs
+                .into_iter()
+                .filter(|&json| name != "RawValue" || json != duplicates)
+            {
+                let expected = Value::parse_with_options(json, options()).err().unwrap();
+                let e = parse(json, options()).err().unwrap();
+                assert_eq!(
+                    (e.kind(), e.offset(), e.path()),
+                    (expected.kind(), expected.offset(), expected.path()),
+                    "{name}: {json}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let json = deep_document(100_000);
+        let options = || ParseOptions {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        for (name, parse) in representations() {
+            let (text, value) = parse(&json, options()).unwrap();
+            assert!(text == json, "{name}");
+            assert!(value.to_string() == json, "{name}");
+            value.drop_deep();
+        }
+    }
+}
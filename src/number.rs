@@ -1,36 +1,502 @@
+use crate::error::WriteError;
+use crate::options::{NonFiniteFloats, WriteOptions};
+use crate::value::Value;
 use std::fmt::Display;
 
-#[derive(Debug, Clone, PartialEq)]
+// 解析时整数优先用Int，超出i64时依次尝试UInt、Int128（负数）或UInt128（正数），都放不下才用Float
+// 启用arbitrary_precision时解析结果一律是Raw，保存数字的原始文本
+// 以后可能加入新的表示方式，crate外的match需要通配分支
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Number {
     Int(i64),
     UInt(u64),
+    Int128(i128),
+    UInt128(u128),
     Float(f64),
+    #[cfg(feature = "arbitrary_precision")]
+    Raw(String),
 }
 
+// 按默认的WriteOptions输出JSON文本
 impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Number::Int(n) => n.fmt(f),
             Number::UInt(n) => n.fmt(f),
-            Number::Float(n) => n.fmt(f),
+            Number::Int128(n) => n.fmt(f),
+            Number::UInt128(n) => n.fmt(f),
+            Number::Float(n) => match write_float(*n, &WriteOptions::default()) {
+                Ok(s) => f.write_str(&s),
+                Err(_) => Err(std::fmt::Error),
+            },
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(s) => f.write_str(s),
         }
     }
 }
 
-impl Into<Number> for i64 {
-    fn into(self) -> Number {
-        Number::Int(self)
+// 输出能被解析回同一个值的最短数字，指数在[-7, 21)之外时用科学计数法，与JavaScript相同
+pub(crate) fn write_float<F>(n: F, options: &WriteOptions) -> Result<String, WriteError>
+where
+    F: Display + std::fmt::LowerExp + Into<f64> + Copy,
+{
+    let value: f64 = n.into();
+    if !value.is_finite() {
+        return match options.non_finite_floats {
+            NonFiniteFloats::Error => Err(WriteError::NonFiniteFloat),
+            NonFiniteFloats::Null => Ok("null".to_string()),
+            NonFiniteFloats::String if value.is_nan() => Ok("\"NaN\"".to_string()),
+            NonFiniteFloats::String if value > 0.0 => Ok("\"Infinity\"".to_string()),
+            NonFiniteFloats::String => Ok("\"-Infinity\"".to_string()),
+        };
     }
+    // LowerExp和Display都输出最短的有效数字，区别只在于记数法
+    let scientific = format!("{n:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if !(-7 < exponent && exponent < 21) {
+        return Ok(format!("{mantissa}e{exponent}"));
+    }
+    let mut plain = n.to_string();
+    if !plain.contains('.') {
+        if options.float_suffix {
+            plain.push_str(".0");
+        } else if value.abs() >= 2f64.powi(63) {
+            // 超出i64范围的整数值会被解析为128位整数，写成科学记数法使它仍被读作浮点数
+            return Ok(format!("{mantissa}e{exponent}"));
+        }
+    }
+    Ok(plain)
 }
 
-impl Into<Number> for u64 {
-    fn into(self) -> Number {
-        Number::UInt(self)
+// 整数按数值比较，与使用哪个变体无关；整数与浮点数总是不相等
+// Raw按文本有无小数点或指数区分整数和浮点数，与其他数比较时都按精确的十进制值：
+// Float取它精确的十进制展开（有限的f64总能写成有限位小数），因此比较既精确又可传递
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Number::Float(a), Number::Float(b)) => a == b,
+            _ if self.is_integer() != other.is_integer() => false,
+            #[cfg(feature = "arbitrary_precision")]
+            (Number::Raw(_), _) | (_, Number::Raw(_)) => match (self.to_decimal(), other.to_decimal()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+            _ => self.as_i128() == other.as_i128() && self.as_u128() == other.as_u128(),
+        }
     }
 }
 
-impl Into<Number> for f64 {
-    fn into(self) -> Number {
-        Number::Float(self)
+impl Number {
+    // 按JSON语法解析一个数字，前后不能有空白
+    pub fn from_literal(s: &str) -> Option<Number> {
+        match &Value::parse(s) {
+            Ok(Value::Number(n)) if s.trim() == s => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    // Raw保存的原始文本
+    pub fn as_literal(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    // 整数变体返回true，即使Float的值恰好是整数也返回false；Raw按文本中有无小数点或指数判断
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Float(_) => false,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(s) => !s.contains(['.', 'e', 'E']),
+            _ => true,
+        }
+    }
+
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    pub fn is_f64(&self) -> bool {
+        !self.is_integer()
+    }
+
+    // 整数且在i64范围内时返回Some
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|n| i64::try_from(n).ok())
+    }
+
+    // 非负整数且在u64范围内时返回Some
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_u128().and_then(|n| u64::try_from(n).ok())
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Number::Int(n) => Some(n.into()),
+            Number::UInt(n) => Some(n.into()),
+            Number::Int128(n) => Some(n),
+            Number::UInt128(n) => i128::try_from(n).ok(),
+            Number::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(_) if !self.is_integer() => None,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(ref s) => Decimal::parse(s).to_integer()?.parse().ok(),
+        }
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match *self {
+            Number::Int(n) => u128::try_from(n).ok(),
+            Number::UInt(n) => Some(n.into()),
+            Number::Int128(n) => u128::try_from(n).ok(),
+            Number::UInt128(n) => Some(n),
+            Number::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(_) if !self.is_integer() => None,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(ref s) => Decimal::parse(s).to_integer()?.parse().ok(),
+        }
+    }
+
+    // 整数转换为最接近的f64，可能丢失精度；Raw超出f64范围时返回None
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Number::Int(n) => Some(n as f64),
+            Number::UInt(n) => Some(n as f64),
+            Number::Int128(n) => Some(n as f64),
+            Number::UInt128(n) => Some(n as f64),
+            Number::Float(n) => Some(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(ref s) => s.parse().ok().filter(|n: &f64| n.is_finite()),
+        }
+    }
+
+    // 精确的十进制值；NaN和无穷大没有十进制值
+    #[cfg(feature = "arbitrary_precision")]
+    fn to_decimal(&self) -> Option<Decimal> {
+        match *self {
+            Number::Float(n) => Decimal::from_f64(n),
+            Number::Raw(ref s) => Some(Decimal::parse(s)),
+            _ => Some(Decimal::parse(&self.to_string())),
+        }
+    }
+}
+
+// 十进制数的规范形式：值为 ±digits × 10^exponent，digits没有前导零和末尾零，零表示为空串
+#[cfg(feature = "arbitrary_precision")]
+#[derive(PartialEq)]
+struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl Decimal {
+    // s必须已经符合JSON数字语法
+    fn parse(s: &str) -> Decimal {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            // 指数大到放不下i64时取一个足够大的值，只影响比较两个这样的数
+            Some((mantissa, exp)) => {
+                let exp = exp.strip_prefix('+').unwrap_or(exp);
+                let limit = if exp.starts_with('-') {
+                    i64::MIN / 2
+                } else {
+                    i64::MAX / 2
+                };
+                (
+                    mantissa,
+                    exp.parse::<i64>().unwrap_or(limit).clamp(i64::MIN / 2, i64::MAX / 2),
+                )
+            }
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{int}{frac}");
+        let digits = digits.trim_start_matches('0');
+        let trimmed = digits.trim_end_matches('0');
+        if trimmed.is_empty() {
+            return Decimal {
+                negative: false,
+                digits: String::new(),
+                exponent: 0,
+            };
+        }
+        Decimal {
+            negative,
+            digits: trimmed.to_string(),
+            exponent: exponent - frac.len() as i64 + (digits.len() - trimmed.len()) as i64,
+        }
+    }
+
+    // 有限的f64都可以写成 m × 2^e；e < 0 时等于 m × 5^-e × 10^e，十进制位数有限
+    fn from_f64(n: f64) -> Option<Decimal> {
+        if !n.is_finite() {
+            return None;
+        }
+        let bits = n.to_bits();
+        let biased = ((bits >> 52) & 0x7FF) as i64;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = match biased {
+            0 => (fraction, -1074),
+            _ => (fraction | (1 << 52), biased - 1075),
+        };
+        // 十进制数字，低位在前
+        let mut digits: Vec<u8> = mantissa.to_string().bytes().rev().map(|b| b - b'0').collect();
+        let factor = if exponent < 0 { 5 } else { 2 };
+        for _ in 0..exponent.unsigned_abs() {
+            let mut carry = 0;
+            for digit in digits.iter_mut() {
+                let product = *digit * factor + carry;
+                *digit = product % 10;
+                carry = product / 10;
+            }
+            if carry > 0 {
+                digits.push(carry);
+            }
+        }
+        let sign = if n.is_sign_negative() { "-" } else { "" };
+        let digits: String = digits.iter().rev().map(|&d| char::from(b'0' + d)).collect();
+        Some(Decimal::parse(&format!("{sign}{digits}e{}", exponent.min(0))))
+    }
+
+    // 是整数时返回不带指数的十进制文本；位数超过128位整数范围时返回None
+    fn to_integer(&self) -> Option<String> {
+        if self.exponent < 0 || self.digits.len() as i64 + self.exponent > 40 {
+            return None;
+        }
+        if self.digits.is_empty() {
+            return Some("0".to_string());
+        }
+        let sign = if self.negative { "-" } else { "" };
+        Some(format!("{sign}{}{}", self.digits, "0".repeat(self.exponent as usize)))
+    }
+}
+
+macro_rules! from_integer {
+    ($variant:ident: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(val: $ty) -> Self {
+                    Number::$variant(val.into())
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(Int: i8, i16, i32, i64);
+from_integer!(UInt: u8, u16, u32, u64);
+from_integer!(Int128: i128);
+from_integer!(UInt128: u128);
+
+impl From<f32> for Number {
+    fn from(val: f32) -> Self {
+        Number::Float(val.into())
+    }
+}
+
+impl From<f64> for Number {
+    fn from(val: f64) -> Self {
+        Number::Float(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_accessors() {
+        let n = Number::from(-3);
+        assert!(n.is_integer() && n.is_i64() && !n.is_u64() && !n.is_f64());
+        assert_eq!((n.as_i64(), n.as_u64(), n.as_f64()), (Some(-3), None, Some(-3.0)));
+
+        let n = Number::from(u64::MAX);
+        assert!(n.is_integer() && !n.is_i64() && n.is_u64());
+        assert_eq!((n.as_i64(), n.as_u64()), (None, Some(u64::MAX)));
+        assert_eq!(Number::from(7u8).as_i64(), Some(7));
+
+        let n = Number::from(2.0);
+        assert!(!n.is_integer() && n.is_f64());
+        assert_eq!((n.as_i64(), n.as_u64(), n.as_f64()), (None, None, Some(2.0)));
+        assert_eq!(Number::from(0.5f32), Number::Float(0.5));
+
+        let n = Number::from(i128::MIN);
+        assert!(n.is_integer() && !n.is_i64() && !n.is_u64());
+        assert_eq!((n.as_i128(), n.as_u128()), (Some(i128::MIN), None));
+        let n = Number::from(u128::MAX);
+        assert_eq!((n.as_i128(), n.as_u128()), (None, Some(u128::MAX)));
+    }
+
+    #[test]
+    fn number_eq() {
+        assert_eq!(Number::Int(5), Number::UInt(5));
+        assert_eq!(Number::UInt128(5), Number::Int128(5));
+        assert_eq!(Number::Int(-5), Number::Int128(-5));
+        assert_eq!(Number::UInt(u64::MAX), Number::UInt128(u64::MAX.into()));
+        assert_ne!(Number::Int(-1), Number::UInt(u64::MAX));
+        assert_ne!(Number::Int128(-1), Number::UInt128(u128::MAX));
+        assert_ne!(Number::Int(1), Number::Float(1.0));
+        assert_eq!(Number::Float(1.5), Number::Float(1.5));
+    }
+
+    #[test]
+    fn float_format() {
+        let format = |n: f64| write_float(n, &WriteOptions::default()).unwrap();
+        assert_eq!(format(1.0), "1");
+        assert_eq!(format(-0.0), "-0");
+        assert_eq!(format(0.1), "0.1");
+        assert_eq!(format(1.5e300), "1.5e300");
+        assert_eq!(format(1e20), "1e20");
+        assert_eq!(format(-2f64.powi(63)), "-9.223372036854776e18");
+        assert_eq!(format(2f64.powi(62)), "4611686018427388000");
+        assert_eq!(format(1e21), "1e21");
+        assert_eq!(format(1e-6), "0.000001");
+        assert_eq!(format(1.25e-7), "1.25e-7");
+        assert_eq!(format(f64::MAX), "1.7976931348623157e308");
+        assert_eq!(format(5e-324), "5e-324");
+        assert_eq!(write_float(0.1f32, &WriteOptions::default()).unwrap(), "0.1");
+
+        let options = |non_finite_floats, float_suffix| WriteOptions {
+            non_finite_floats,
+            float_suffix,
+        };
+        assert_eq!(write_float(2.0, &options(NonFiniteFloats::Null, true)).unwrap(), "2.0");
+        assert_eq!(
+            write_float(-0.0, &options(NonFiniteFloats::Null, true)).unwrap(),
+            "-0.0"
+        );
+        assert_eq!(
+            write_float(f64::NAN, &options(NonFiniteFloats::Null, true)).unwrap(),
+            "null"
+        );
+        assert_eq!(
+            write_float(f64::NEG_INFINITY, &options(NonFiniteFloats::String, true)).unwrap(),
+            "\"-Infinity\""
+        );
+        assert_eq!(
+            write_float(f64::INFINITY, &options(NonFiniteFloats::Error, true)),
+            Err(WriteError::NonFiniteFloat)
+        );
+
+        // 各种浮点数都能原样解析回来
+        for n in [
+            0.1,
+            1.0 / 3.0,
+            2.0f64.powi(70),
+            123456.789e-200,
+            f64::MIN_POSITIVE,
+            -f64::EPSILON,
+        ] {
+            assert_eq!(Number::from_literal(&format(n)).unwrap().as_f64(), Some(n));
+            assert!(!Number::from_literal(&format(n)).unwrap().is_integer());
+        }
+    }
+
+    #[test]
+    fn number_from_literal() {
+        let n = Number::from_literal("-12").unwrap();
+        assert_eq!(n, Number::Int(-12));
+        assert_eq!(n.to_string(), "-12");
+        assert!(Number::from_literal(" 1").is_none());
+        assert!(Number::from_literal("01").is_none());
+        assert!(Number::from_literal("1.").is_none());
+        assert!(Number::from_literal("\"1\"").is_none());
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn number_raw() {
+        let literal = "123456789012345678901234567890.123456789012345678901234567890";
+        let n = Number::from_literal(literal).unwrap();
+        assert_eq!(n.as_literal(), Some(literal));
+        assert_eq!(n.to_string(), literal);
+        assert!(!n.is_integer() && n.as_i128().is_none());
+
+        // 数值相同、写法不同的数相等，但整数与浮点数不相等；浮点数按精确的十进制值比较
+        let raw = |s: &str| Number::from_literal(s).unwrap();
+        assert_eq!(raw("1.50"), raw("0.15e1"));
+        assert_eq!(raw("-0"), raw("0"));
+        assert_eq!(raw("-0.0"), raw("0.0e10"));
+        assert_eq!(raw("100"), Number::Int(100));
+        assert_eq!(raw("100"), Number::UInt128(100));
+        assert_eq!(raw("2.5"), Number::Float(2.5));
+        assert_ne!(raw("1e-10000"), Number::Float(0.0));
+        assert_ne!(raw("0.10000000000000001"), raw("0.1"));
+        assert_ne!(raw("1.0000000000000000000001"), raw("1.0"));
+        assert_ne!(raw("0.1"), Number::Float(0.1));
+        assert_eq!(
+            raw("0.1000000000000000055511151231257827021181583404541015625"),
+            Number::Float(0.1)
+        );
+        assert_eq!(raw("-2.5e-1"), Number::Float(-0.25));
+        assert_eq!(raw("1180591620717411303424.0"), Number::Float(2f64.powi(70)));
+        assert_ne!(raw("4.9406564584124654e-324"), Number::Float(5e-324));
+        assert_eq!(raw("0.0"), Number::Float(-0.0));
+        assert_eq!(raw("1.7976931348623157e308"), raw("17976931348623157e292"));
+        assert_eq!(raw("9007199254740993.0"), raw("9007199254740993e0"));
+        assert_ne!(raw("9007199254740993.0"), Number::Float(9007199254740992.0));
+        assert_eq!(raw("1e400"), raw("10e399"));
+        assert_ne!(raw("1e400"), raw("1e401"));
+        assert_ne!(raw("1e400"), Number::Float(f64::INFINITY));
+        assert_ne!(raw("1e2"), Number::Int(100));
+        assert_ne!(raw("1.0"), raw("1"));
+        assert_ne!(
+            raw("100000000000000000000000000001"),
+            raw("100000000000000000000000000000")
+        );
+        assert_ne!(raw("-1"), raw("1"));
+        assert_ne!(raw("1"), Number::Float(f64::NAN));
+
+        // 相等关系可传递：与同一个数相等的两个数彼此相等
+        let numbers = [
+            raw("1"),
+            raw("1.0"),
+            raw("10e-1"),
+            Number::Int(1),
+            Number::UInt128(1),
+            Number::Float(1.0),
+            raw("0.1"),
+            raw("0.10000000000000001"),
+            raw("0.1000000000000000055511151231257827021181583404541015625"),
+            Number::Float(0.1),
+            raw("1e-10000"),
+            raw("0"),
+            Number::Float(0.0),
+        ];
+        for a in &numbers {
+            for b in &numbers {
+                for c in &numbers {
+                    if a == b && b == c {
+                        assert_eq!(a, c, "{a} {b} {c}");
+                    }
+                }
+            }
+        }
+
+        // 与Float(15.0)相同，带小数点或指数的数不是整数
+        assert_eq!(raw("1.5e1").as_i64(), None);
+        assert_eq!(raw("1.5e1").as_i128(), Number::Float(15.0).as_i128());
+        assert_eq!(raw("15").as_u128(), Some(15));
+        assert_eq!(
+            raw("-170141183460469231731687303715884105728").as_i128(),
+            Some(i128::MIN)
+        );
+        assert_eq!(raw("3.4028236692093846347e38").as_u128(), None);
+        assert_eq!(raw("1e400").as_f64(), None);
+        assert_eq!(raw("1e400").to_string(), "1e400");
     }
 }
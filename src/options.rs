@@ -0,0 +1,77 @@
+// 解析选项，通常只修改需要的字段，其余使用默认值：
+// ParseOptions { max_depth: 32, ..Default::default() }
+//
+// 各项限制在解析过程中检查，超出时立即返回对应的错误，不会先分配内存再检查
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    // 数组和对象允许的最大嵌套层数，超过时报告DepthLimitExceeded。
+    // 解析器不使用递归，设为usize::MAX可以解析任意深的文档；但得到的值直接drop时会递归释放，
+    // 嵌套极深时可能栈溢出，需要改用drop_deep释放
+    pub max_depth: usize,
+    // 输入的最大字节数，超过时报告DocumentTooLarge
+    pub max_document_size: usize,
+    // 字符串（包括键）转义后的最大字节数，超过时报告StringTooLong
+    pub max_string_length: usize,
+    // 单个数组的最大元素个数，超过时报告ArrayTooLong
+    pub max_array_length: usize,
+    // 单个对象的最大成员个数，超过时报告ObjectTooLong
+    pub max_object_length: usize,
+    // 整个文档中值（包括数组和对象本身）的最大个数，超过时报告TooManyNodes
+    pub max_nodes: usize,
+    // 构建Value时如何处理对象中重复的键；Parser的事件流总是原样报告每个成员
+    pub duplicate_keys: DuplicateKeys,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    // 报告DuplicateKey错误
+    Error,
+    // 保留第一次出现的值
+    FirstWins,
+    // 保留最后一次出现的值
+    LastWins,
+    // 保留所有的值：键重复时把它的各个值按出现顺序收集为一个数组
+    CollectAll,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_document_size: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_length: usize::MAX,
+            max_nodes: usize::MAX,
+            duplicate_keys: DuplicateKeys::LastWins,
+        }
+    }
+}
+
+// 序列化选项，Value::to_string_with_options和serde的to_string_with_options使用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    // NaN和±Infinity不是合法的JSON数字，按此策略输出
+    pub non_finite_floats: NonFiniteFloats,
+    // 值为整数的浮点数是否写成"1.0"的形式，使它重新解析后仍是浮点数而不是整数；默认写成"1"
+    pub float_suffix: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    // 报告WriteError::NonFiniteFloat
+    Error,
+    // 写成null
+    Null,
+    // 写成字符串"NaN"、"Infinity"或"-Infinity"
+    String,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            non_finite_floats: NonFiniteFloats::Null,
+            float_suffix: false,
+        }
+    }
+}
@@ -0,0 +1,1007 @@
+use crate::context::{Context, Segment};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::number::Number;
+use crate::options::ParseOptions;
+use crate::stack::Stack;
+use std::borrow::Cow;
+use std::io::Read;
+
+// 拉取式解析器产生的事件，字符串和键借用自解析器内部的缓冲区
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    Key(&'a str),
+    EndObject,
+    StartArray,
+    EndArray,
+    String(&'a str),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+enum Container {
+    // 已解析的元素个数，数组的元素个数同时用于生成错误路径中的下标
+    Array(usize),
+    Object(usize),
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Root,
+    ArrayStart,
+    ArrayValue,
+    ObjectStart,
+    ObjectKey,
+    ObjectValue,
+    AfterValue,
+    Done,
+}
+
+// 拉取式(pull)解析器，每次调用next_event返回一个事件，不构建Value树
+pub struct Parser<'a> {
+    context: Context<'a>,
+    containers: Vec<Container>,
+    state: State,
+    // 最近一个字符串或键的内容：从切片解析且不含转义时直接借用输入，否则写入scratch中；
+    // scratch在整个解析过程中重复使用
+    borrowed: Option<&'a str>,
+    scratch: String,
+    options: ParseOptions,
+    // 已开始解析的值的个数
+    nodes: usize,
+    // 最近一个键的起始位置
+    key_offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(json: &'a [u8]) -> Self {
+        Parser::from_context(Context::new(json))
+    }
+
+    pub fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        Parser::from_context(Context::from_reader(reader))
+    }
+
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.context.max_size = options.max_document_size;
+        self.options = options;
+        self
+    }
+
+    fn from_context(context: Context<'a>) -> Self {
+        Self {
+            context,
+            containers: Vec::new(),
+            state: State::Root,
+            borrowed: None,
+            scratch: String::new(),
+            options: ParseOptions::default(),
+            nodes: 0,
+            key_offset: 0,
+        }
+    }
+
+    // 返回下一个事件；整个文档解析完毕后返回None，此时会检查根值之后没有多余的字符
+    pub fn next_event(&mut self) -> Result<Option<Event<'_>>, ParseError> {
+        if let State::Root = self.state {
+            self.context.check_size()?;
+        }
+        loop {
+            Parser::parse_whitespace(&mut self.context)?;
+            match self.state {
+                State::Root | State::ArrayValue => return self.parse_value().map(Some),
+                State::ArrayStart => {
+                    if self.context.bytes().first() == Some(&b']') {
+                        self.context.step();
+                        return Ok(Some(self.end_container()));
+                    }
+                    return self.parse_value().map(Some);
+                }
+                State::ObjectStart => {
+                    if self.context.bytes().first() == Some(&b'}') {
+                        self.context.step();
+                        return Ok(Some(self.end_container()));
+                    }
+                    return self.parse_key().map(Some);
+                }
+                State::ObjectKey => return self.parse_key().map(Some),
+                State::ObjectValue => {
+                    if self.context.bytes().first() != Some(&b':') {
+                        return Err(self.context.error(ParseErrorKind::MissColon));
+                    }
+                    self.context.step();
+                    Parser::parse_whitespace(&mut self.context)?;
+                    let key = self.borrowed_str();
+                    self.context.path.push(Segment::Key(key));
+                    return self.parse_value().map(Some);
+                }
+                State::AfterValue => {
+                    let pos = self.context.offset();
+                    let b = self.context.step();
+                    match (self.containers.last(), b) {
+                        (Some(Container::Array(_)), Some(b',')) => self.state = State::ArrayValue,
+                        (Some(Container::Array(_)), Some(b']')) => return Ok(Some(self.end_container())),
+                        (Some(Container::Array(_)), _) => {
+                            return Err(self.context.error_at(pos, ParseErrorKind::MissCommaOrSquareBracket));
+                        }
+                        (_, Some(b',')) => self.state = State::ObjectKey,
+                        (_, Some(b'}')) => return Ok(Some(self.end_container())),
+                        (_, _) => return Err(self.context.error_at(pos, ParseErrorKind::MissCommaOrCurlyBracket)),
+                    }
+                }
+                State::Done => {
+                    if self.context.bytes().is_empty() {
+                        return Ok(None);
+                    }
+                    return Err(self.context.error(ParseErrorKind::RootNotSingular));
+                }
+            }
+        }
+    }
+
+    // 跳过当前所在容器中剩余的内容，包括其结束事件；
+    // 例如收到StartObject后调用可以跳过整个对象
+    pub fn skip(&mut self) -> Result<(), ParseError> {
+        let depth = self.containers.len();
+        while self.containers.len() >= depth && depth > 0 {
+            if self.next_event()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // 当前所在容器的嵌套层数
+    pub fn depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.context.offset()
+    }
+
+    pub(crate) fn error(&self, kind: ParseErrorKind) -> ParseError {
+        self.context.error(kind)
+    }
+
+    pub(crate) fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        self.context.error_at(offset, kind)
+    }
+
+    #[cfg(feature = "serde")]
+    // 记录原文：end_capture返回从start_capture之后读取的所有字节，包括已从缓冲区丢弃的部分
+    pub(crate) fn start_capture(&mut self) {
+        self.context.start_capture();
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn end_capture(&mut self) -> Vec<u8> {
+        self.context.end_capture()
+    }
+
+    #[cfg(feature = "serde")]
+    // 从Read解析时先读入全部输入，之后可以用buffered_from回头查看已解析过的部分
+    pub(crate) fn read_to_end(&mut self) -> Result<(), ParseError> {
+        self.context.read_to_end()
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn buffered_from(&self, offset: usize) -> Option<&[u8]> {
+        self.context.buffered_from(offset)
+    }
+
+    pub(crate) fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub(crate) fn key_offset(&self) -> usize {
+        self.key_offset
+    }
+
+    // 最近一个String或Key事件的内容，复制出来以便scratch继续使用
+    pub(crate) fn owned_string(&self) -> String {
+        self.current().to_string()
+    }
+
+    // 同owned_string，但不含转义的字符串借用输入而不复制
+    pub(crate) fn borrowed_str(&self) -> Cow<'a, str> {
+        match self.borrowed {
+            Some(s) => Cow::Borrowed(s),
+            None => Cow::Owned(self.scratch.clone()),
+        }
+    }
+
+    pub(crate) fn current(&self) -> &str {
+        self.borrowed.unwrap_or(&self.scratch)
+    }
+
+    fn parse_value(&mut self) -> Result<Event<'_>, ParseError> {
+        if let Some(Container::Array(n)) = self.containers.last() {
+            self.context.path.push(Segment::Index(*n));
+        }
+        self.context.ensure(1)?;
+        if let Some(&b) = self.context.bytes().first() {
+            self.check_limits(b)?;
+        }
+        let event = match self.context.bytes().first() {
+            Some(b'n' | b't' | b'f') => match Parser::parse_literal(&mut self.context)? {
+                Some(b) => Event::Bool(b),
+                None => Event::Null,
+            },
+            Some(b'"') => {
+                self.borrowed =
+                    Parser::parse_string(&mut self.context, self.options.max_string_length, &mut self.scratch)?;
+                self.end_value();
+                return Ok(Event::String(self.current()));
+            }
+            Some(b'[') => {
+                self.context.step();
+                self.containers.push(Container::Array(0));
+                self.state = State::ArrayStart;
+                return Ok(Event::StartArray);
+            }
+            Some(b'{') => {
+                self.context.step();
+                self.containers.push(Container::Object(0));
+                self.state = State::ObjectStart;
+                return Ok(Event::StartObject);
+            }
+            Some(_) => Event::Number(Parser::parse_number(&mut self.context)?),
+            None => return Err(self.context.error(ParseErrorKind::ExpectValue)),
+        };
+        self.end_value();
+        Ok(event)
+    }
+
+    // 在以b开头的值开始解析之前检查嵌套深度、数组长度和值的总数
+    fn check_limits(&mut self, b: u8) -> Result<(), ParseError> {
+        let kind = match self.containers.last() {
+            _ if matches!(b, b'[' | b'{') && self.containers.len() >= self.options.max_depth => {
+                ParseErrorKind::DepthLimitExceeded
+            }
+            Some(Container::Array(n)) if *n >= self.options.max_array_length => ParseErrorKind::ArrayTooLong,
+            _ if self.nodes >= self.options.max_nodes => ParseErrorKind::TooManyNodes,
+            _ => {
+                self.nodes += 1;
+                return Ok(());
+            }
+        };
+        Err(self.context.error(kind))
+    }
+
+    fn parse_key(&mut self) -> Result<Event<'_>, ParseError> {
+        if let Some(Container::Object(n)) = self.containers.last()
+            && *n >= self.options.max_object_length
+        {
+            return Err(self.context.error(ParseErrorKind::ObjectTooLong));
+        }
+        self.key_offset = self.context.offset();
+        match Parser::parse_string(&mut self.context, self.options.max_string_length, &mut self.scratch) {
+            Ok(key) => self.borrowed = key,
+            Err(e) if matches!(e.kind(), ParseErrorKind::StringTooLong | ParseErrorKind::Io(_)) => return Err(e),
+            Err(_) => return Err(self.context.error(ParseErrorKind::MissKey)),
+        }
+        self.state = State::ObjectValue;
+        Ok(Event::Key(self.current()))
+    }
+
+    fn end_container(&mut self) -> Event<'static> {
+        let event = match self.containers.pop() {
+            Some(Container::Array(_)) => Event::EndArray,
+            _ => Event::EndObject,
+        };
+        self.end_value();
+        event
+    }
+
+    // 一个完整的值解析结束：弹出它在路径中的位置，并更新所在容器的状态
+    fn end_value(&mut self) {
+        match self.containers.last_mut() {
+            None => self.state = State::Done,
+            Some(container) => {
+                let (Container::Array(n) | Container::Object(n)) = container;
+                *n += 1;
+                self.context.path.pop();
+                self.state = State::AfterValue;
+            }
+        }
+    }
+
+    pub(crate) fn parse_literal(context: &mut Context) -> Result<Option<bool>, ParseError> {
+        match context.bytes().first() {
+            Some(b'n') => Parser::check_literal(context, "null".as_bytes()).map(|_| None),
+            Some(b't') => Parser::check_literal(context, "true".as_bytes()).map(|_| Some(true)),
+            _ => Parser::check_literal(context, "false".as_bytes()).map(|_| Some(false)),
+        }
+    }
+
+    fn parse_whitespace(context: &mut Context) -> Result<(), ParseError> {
+        loop {
+            let bytes = context.bytes();
+            for (i, &c) in bytes.iter().enumerate() {
+                if !(c == b' ' || c == b'\t' || c == b'\n' || c == b'\r') {
+                    context.advance(i);
+                    return Ok(());
+                }
+            }
+            context.advance(bytes.len());
+            if !context.fill()? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn check_literal(context: &mut Context, literal: &[u8]) -> Result<(), ParseError> {
+        context.ensure(literal.len())?;
+        let bytes = context.bytes();
+        if bytes.len() < literal.len() || &bytes[0..literal.len()] != literal {
+            return Err(context.error(ParseErrorKind::InvalidValue));
+        }
+        context.advance(literal.len());
+        Ok(())
+    }
+
+    fn skip_following_digits(bytes: &[u8], start: usize) -> usize {
+        if start >= bytes.len() {
+            return 0_usize;
+        }
+        let mut count: usize = 0;
+        for &b in bytes[start..].iter() {
+            if !(b.is_ascii_digit()) {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    pub(crate) fn parse_number(context: &mut Context) -> Result<Number, ParseError> {
+        context.ensure_while(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))?;
+        let bytes = context.bytes();
+        let start = context.offset();
+        let mut index_end: usize = 0;
+        let mut is_float: bool = false;
+        // 整数部分
+        if *bytes.first().unwrap() == b'-' {
+            index_end += 1;
+        }
+        {
+            let len_int = Parser::skip_following_digits(bytes, index_end);
+            if len_int == 0 {
+                return Err(context.error_at(start + index_end, ParseErrorKind::InvalidValue));
+            }
+            if bytes[index_end] == b'0' && len_int > 1 {
+                return Err(context.error_at(start + index_end + 1, ParseErrorKind::RootNotSingular));
+            }
+            index_end += len_int;
+        }
+
+        // 小数部分
+        if index_end < bytes.len() && bytes[index_end] == b'.' {
+            index_end += 1;
+            is_float = true;
+            let len_int = Parser::skip_following_digits(bytes, index_end);
+            if len_int == 0 {
+                return Err(context.error_at(start + index_end, ParseErrorKind::InvalidValue));
+            }
+            index_end += len_int;
+        }
+
+        // 指数部分
+        if index_end < bytes.len() && (bytes[index_end] == b'e' || bytes[index_end] == b'E') {
+            index_end += 1;
+            is_float = true;
+            // 正负号
+            if index_end < bytes.len() && (bytes[index_end] == b'+' || bytes[index_end] == b'-') {
+                index_end += 1;
+            }
+            let len_int = Parser::skip_following_digits(bytes, index_end);
+            if len_int == 0 {
+                return Err(context.error_at(start + index_end, ParseErrorKind::InvalidValue));
+            }
+            index_end += len_int;
+        }
+
+        match Parser::convert_number(&bytes[0..index_end], is_float) {
+            Some(num) => {
+                context.advance(index_end);
+                Ok(num)
+            }
+            None => Err(context.error(ParseErrorKind::NumberTooBig)),
+        }
+    }
+
+    // 启用arbitrary_precision时保留原始文本，不会超出范围
+    #[cfg(feature = "arbitrary_precision")]
+    pub(crate) fn convert_number(bytes: &[u8], _is_float: bool) -> Option<Number> {
+        // 数字只含ASCII字符，from_utf8不会失败
+        std::str::from_utf8(bytes).ok().map(|s| Number::Raw(s.to_string()))
+    }
+
+    // 把已通过语法检查的数字转换为二进制，超出范围时返回None
+    // 整数尽量无损保存，只有小数、指数形式或超出128位的整数才转换为f64
+    // 常见情况直接在字节上累加，少数情况才交给标准库解析
+    #[cfg(not(feature = "arbitrary_precision"))]
+    pub(crate) fn convert_number(bytes: &[u8], is_float: bool) -> Option<Number> {
+        let (negative, digits) = match bytes.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            _ => (false, bytes),
+        };
+        if !is_float {
+            if let Some(num) = Parser::accumulate_integer(digits, negative) {
+                return Some(num);
+            }
+        } else if let Some(num) = Parser::fast_float(digits) {
+            return Some(Number::Float(if negative { -num } else { num }));
+        }
+        Parser::convert_number_slow(bytes, is_float)
+    }
+
+    // 在u64范围内累加整数，溢出时返回None
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn accumulate_integer(digits: &[u8], negative: bool) -> Option<Number> {
+        let mut value: u64 = 0;
+        for &b in digits {
+            value = value.checked_mul(10)?.checked_add(u64::from(b - b'0'))?;
+        }
+        let num = if negative {
+            if value <= i64::MAX as u64 + 1 {
+                Number::Int((value as i64).wrapping_neg())
+            } else {
+                Number::Int128(-i128::from(value))
+            }
+        } else if value <= i64::MAX as u64 {
+            Number::Int(value as i64)
+        } else {
+            Number::UInt(value)
+        };
+        Some(num)
+    }
+
+    // Clinger快速路径：尾数不超过2^53且10的幂可以精确表示时，一次乘除即可得到正确舍入的结果
+    // 其余情况返回None
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn fast_float(digits: &[u8]) -> Option<f64> {
+        // 10^0..=10^22都能用f64精确表示
+        const POW10: [f64; 23] = [
+            1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16, 1e17, 1e18,
+            1e19, 1e20, 1e21, 1e22,
+        ];
+        const MAX_MANTISSA: u64 = 1 << 53;
+
+        let mut mantissa: u64 = 0;
+        let mut significant: usize = 0;
+        let mut exponent: i64 = 0;
+        let mut index = 0;
+        // 整数部分和小数部分，跳过前导零，最多累加19位有效数字
+        let mut in_fraction = false;
+        while index < digits.len() {
+            let b = digits[index];
+            if b == b'.' {
+                in_fraction = true;
+            } else if b.is_ascii_digit() {
+                if mantissa != 0 || b != b'0' {
+                    significant += 1;
+                    if significant > 19 {
+                        return None;
+                    }
+                }
+                mantissa = mantissa * 10 + u64::from(b - b'0');
+                if in_fraction {
+                    exponent -= 1;
+                }
+            } else {
+                break;
+            }
+            index += 1;
+        }
+        // 指数部分，过大的指数饱和处理即可，反正不会走快速路径
+        if index < digits.len() {
+            index += 1;
+            let negative = match digits.get(index) {
+                Some(b'-') => {
+                    index += 1;
+                    true
+                }
+                Some(b'+') => {
+                    index += 1;
+                    false
+                }
+                _ => false,
+            };
+            let mut exp: i64 = 0;
+            for &b in &digits[index..] {
+                exp = (exp * 10 + i64::from(b - b'0')).min(1 << 20);
+            }
+            exponent += if negative { -exp } else { exp };
+        }
+
+        if mantissa == 0 {
+            return Some(0.0);
+        }
+        if mantissa > MAX_MANTISSA {
+            return None;
+        }
+        if (-22..=22).contains(&exponent) {
+            let value = mantissa as f64;
+            return Some(if exponent < 0 {
+                value / POW10[(-exponent) as usize]
+            } else {
+                value * POW10[exponent as usize]
+            });
+        }
+        // 尾数较小时，可以把多出的指数先乘进整数尾数
+        if exponent > 22 && exponent <= 22 + 15 {
+            let mantissa = mantissa.checked_mul(10u64.pow((exponent - 22) as u32))?;
+            if mantissa <= MAX_MANTISSA {
+                return Some(mantissa as f64 * POW10[22]);
+            }
+        }
+        None
+    }
+
+    // 快速路径无法处理时，交给标准库解析
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn convert_number_slow(bytes: &[u8], is_float: bool) -> Option<Number> {
+        // 数字只含ASCII字符，from_utf8不会失败
+        let number_str = std::str::from_utf8(bytes).ok()?;
+        if !is_float {
+            if let Ok(num) = number_str.parse::<i64>() {
+                return Some(Number::Int(num));
+            }
+            if let Ok(num) = number_str.parse::<u64>() {
+                return Some(Number::UInt(num));
+            }
+            let num = if number_str.starts_with('-') {
+                number_str.parse::<i128>().map(Number::Int128).ok()
+            } else {
+                number_str.parse::<u128>().map(Number::UInt128).ok()
+            };
+            if num.is_some() {
+                return num;
+            }
+        }
+        match number_str.parse::<f64>() {
+            Ok(num) if num.is_finite() => Some(Number::Float(num)),
+            _ => None,
+        }
+    }
+
+    fn hex4_to_u32(hex4: &[u8]) -> Option<u32> {
+        assert_eq!(hex4.len(), 4);
+        let mut value = 0u32;
+        for &b in hex4 {
+            value = (value << 4) | Parser::hex_digit(b)?;
+        }
+        Some(value)
+    }
+
+    pub(crate) fn hex_digit(b: u8) -> Option<u32> {
+        let digit = match b {
+            b'0'..=b'9' => b - b'0',
+            b'a'..=b'f' => b - b'a' + 10,
+            b'A'..=b'F' => b - b'A' + 10,
+            _ => return None, // 非法字符
+        };
+        Some(u32::from(digit))
+    }
+
+    // 单字符转义序列`\x`对应的字节，`\u`不在此列
+    pub(crate) fn unescape(b: u8) -> Option<u8> {
+        match b {
+            b'"' => Some(b'\"'),
+            b'\\' => Some(b'\\'),
+            b'/' => Some(b'/'),
+            b'b' => Some(b'\x08'),
+            b'f' => Some(b'\x0C'),
+            b'n' => Some(b'\n'),
+            b'r' => Some(b'\r'),
+            b't' => Some(b'\t'),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn encode_utf8(stack: &mut Stack<u8>, c: u32) -> Option<()> {
+        let ch = char::from_u32(c)?;
+        let mut buf = [0; 4]; // UTF-8 最多需要 4 个字节
+        let bytes = ch.encode_utf8(&mut buf);
+        let utf8_bytes: &[u8] = bytes.as_bytes(); // 获取 &[u8]
+        stack.push_bytes(utf8_bytes);
+        Some(())
+    }
+
+    // 解析字符串：从切片解析且不含转义时返回借用的输入，否则把内容写入scratch并返回None
+    pub(crate) fn parse_string(
+        context: &mut Context<'a>,
+        max_len: usize,
+        scratch: &mut String,
+    ) -> Result<Option<&'a str>, ParseError> {
+        if let Some(bytes) = context.borrowed_bytes()
+            && bytes.first() == Some(&b'"')
+        {
+            let len = Parser::find_special(&bytes[1..]);
+            if bytes.get(1 + len) == Some(&b'"')
+                && len <= max_len
+                && let Ok(s) = std::str::from_utf8(&bytes[1..1 + len])
+            {
+                context.advance(len + 2);
+                return Ok(Some(s));
+            }
+        }
+        Parser::parse_string_raw(context, max_len, scratch)?;
+        Ok(None)
+    }
+
+    // 返回第一个引号、反斜杠或控制字符的位置，没有时返回bytes.len()。
+    // 每次用一个u64检查8个字节：某个字节命中时该字节的最高位为1，
+    // 更高的字节可能因借位误报，但最低的命中位总是准确的
+    fn find_special(bytes: &[u8]) -> usize {
+        const ONES: u64 = u64::from_ne_bytes([0x01; 8]);
+        const HIGHS: u64 = u64::from_ne_bytes([0x80; 8]);
+        let has_zero = |v: u64| v.wrapping_sub(ONES) & !v;
+        let mut i = 0;
+        while let Some(chunk) = bytes.get(i..i + 8) {
+            let word = u64::from_le_bytes(chunk.try_into().unwrap());
+            let quote = has_zero(word ^ (ONES * u64::from(b'"')));
+            let backslash = has_zero(word ^ (ONES * u64::from(b'\\')));
+            let control = word.wrapping_sub(ONES * 0x20) & !word;
+            let mask = (quote | backslash | control) & HIGHS;
+            if mask != 0 {
+                return i + (mask.trailing_zeros() / 8) as usize;
+            }
+            i += 8;
+        }
+        bytes[i..]
+            .iter()
+            .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+            .map_or(bytes.len(), |n| i + n)
+    }
+
+    // 逐段解析字符串写入out：不需转义的连续字节整段复制，并且只在复制时检查一次UTF-8
+    fn parse_string_raw(context: &mut Context, max_len: usize, out: &mut String) -> Result<(), ParseError> {
+        // 一个转义序列最多6个字节，至少生成1个字节
+        context.ensure_string(max_len.saturating_mul(6).saturating_add(2))?;
+        let bytes = context.bytes();
+        let start = context.offset();
+        if bytes.len() < 2 || *bytes.first().unwrap() != b'"' {
+            return Err(context.error(ParseErrorKind::MissQuotationMark));
+        }
+        out.clear();
+        // 转义之外的字节中第一个非法UTF-8的位置；找到右引号时才报告，与其他错误的优先级保持不变
+        let mut invalid_utf8: Option<usize> = None;
+        // 已生成的字节数，含非法UTF-8的片段也计入
+        let mut len = 0;
+        let mut i_context = 1;
+        loop {
+            let run = Parser::find_special(&bytes[i_context..]);
+            if run > 0 {
+                let segment = &bytes[i_context..i_context + run];
+                match std::str::from_utf8(segment) {
+                    Ok(s) if invalid_utf8.is_none() => out.push_str(s),
+                    Ok(_) => {}
+                    Err(e) => {
+                        invalid_utf8.get_or_insert(start + i_context + e.valid_up_to());
+                    }
+                }
+                len += run;
+                i_context += run;
+            }
+            if len > max_len && i_context < bytes.len() {
+                return Err(context.error_at(start, ParseErrorKind::StringTooLong));
+            }
+            let pos = start + i_context;
+            let error = |kind| context.error_at(pos, kind);
+            match bytes.get(i_context) {
+                None => break,
+                Some(b'"') => {
+                    if let Some(offset) = invalid_utf8 {
+                        return Err(context.error_at(offset, ParseErrorKind::InvalidUtf8));
+                    }
+                    context.advance(i_context + 1);
+                    return Ok(());
+                }
+                Some(b'\\') => {
+                    // 处理转义序列
+                    if i_context + 1 >= bytes.len() {
+                        break;
+                    }
+                    let c = match bytes[i_context + 1] {
+                        b'u' => {
+                            if i_context + 6 >= bytes.len() {
+                                return Err(error(ParseErrorKind::InvalidUnicodeHex));
+                            }
+                            let Some(high_surrogate) = Parser::hex4_to_u32(&bytes[i_context + 2..i_context + 6]) else {
+                                return Err(error(ParseErrorKind::InvalidUnicodeHex));
+                            };
+                            if (0xD800..=0xDBFF).contains(&high_surrogate) {
+                                // 代码对的高代理项（high surrogate）
+                                if !(i_context + 12 < bytes.len()
+                                    && (bytes[i_context + 6] == b'\\' && bytes[i_context + 7] == b'u'))
+                                {
+                                    return Err(error(ParseErrorKind::InvalidUnicodeSurrogate));
+                                }
+                                let Some(low_surrogate) = Parser::hex4_to_u32(&bytes[i_context + 8..i_context + 12])
+                                else {
+                                    return Err(error(ParseErrorKind::InvalidUnicodeHex));
+                                };
+                                if !(0xDC00..=0xDFFF).contains(&low_surrogate) {
+                                    return Err(error(ParseErrorKind::InvalidUnicodeSurrogate));
+                                }
+                                i_context += 10;
+                                0x10000 + (high_surrogate - 0xD800) * 0x400 + (low_surrogate - 0xDC00)
+                            } else {
+                                i_context += 4;
+                                high_surrogate
+                            }
+                        }
+                        b => match Parser::unescape(b) {
+                            Some(c) => u32::from(c),
+                            None => return Err(error(ParseErrorKind::InvalidStringEscape)),
+                        },
+                    };
+                    // 单独的低代理项不是合法的字符
+                    let Some(c) = char::from_u32(c) else {
+                        return Err(error(ParseErrorKind::InvalidUnicodeSurrogate));
+                    };
+                    if invalid_utf8.is_none() {
+                        out.push(c);
+                    }
+                    len += c.len_utf8();
+                    i_context += 2;
+                }
+                Some(_) => return Err(error(ParseErrorKind::InvalidStringChar)),
+            }
+        }
+        Err(context.error_at(start + i_context, ParseErrorKind::MissQuotationMark))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    fn events(json: &str) -> Result<Vec<String>, ParseError> {
+        let mut parser = Parser::new(json.as_bytes());
+        let mut result = Vec::new();
+        while let Some(event) = parser.next_event()? {
+            result.push(format!("{event:?}"));
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn pull_events() {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let numbers = ["Number(Int(1))", "Number(Float(-2.5))"];
+        #[cfg(feature = "arbitrary_precision")]
+        let numbers = [r#"Number(Raw("1"))"#, r#"Number(Raw("-2.5"))"#];
+        assert_eq!(
+            events(r#" {"a": [1, -2.5, "x\ny"], "b": {}, "c": [], "d": [null, true, false]} "#).unwrap(),
+            [
+                "StartObject",
+                r#"Key("a")"#,
+                "StartArray",
+                numbers[0],
+                numbers[1],
+                r#"String("x\ny")"#,
+                "EndArray",
+                r#"Key("b")"#,
+                "StartObject",
+                "EndObject",
+                r#"Key("c")"#,
+                "StartArray",
+                "EndArray",
+                r#"Key("d")"#,
+                "StartArray",
+                "Null",
+                "Bool(true)",
+                "Bool(false)",
+                "EndArray",
+                "EndObject",
+            ]
+        );
+        assert_eq!(events(r#""abc""#).unwrap(), [r#"String("abc")"#]);
+    }
+
+    #[test]
+    fn pull_errors() {
+        assert_eq!(
+            events("[1, 2").err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrSquareBracket
+        );
+        assert_eq!(events("[1] 2").err().unwrap().kind(), ParseErrorKind::RootNotSingular);
+        assert_eq!(events(r#"{"a" 1}"#).err().unwrap().kind(), ParseErrorKind::MissColon);
+
+        // 出错之前的事件已经返回给调用者
+        let mut parser = Parser::new(br#"[true, nul]"#);
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartArray));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::Bool(true)));
+        let e = parser.next_event().err().unwrap();
+        assert_eq!(
+            (e.kind(), e.offset(), e.path_string().as_str()),
+            (ParseErrorKind::InvalidValue, 7, "$[1]")
+        );
+    }
+
+    #[test]
+    fn pull_skip_and_build_subtree() {
+        let json = r#"[{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": ["c"]}, {"id": 3}]"#;
+        let mut parser = Parser::new(json.as_bytes());
+        let mut tags = Vec::new();
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartArray));
+        while let Some(Event::StartObject) = parser.next_event().unwrap() {
+            loop {
+                match parser.next_event().unwrap() {
+                    Some(Event::Key("tags")) => tags.push(Value::from_parser(&mut parser).unwrap()),
+                    Some(Event::Key(_)) => {
+                        Value::from_parser(&mut parser).unwrap();
+                    }
+                    _ => break,
+                }
+            }
+        }
+        assert_eq!(
+            tags,
+            [Value::parse(r#"["a","b"]"#).unwrap(), Value::parse(r#"["c"]"#).unwrap()]
+        );
+
+        let mut parser = Parser::new(json.as_bytes());
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartArray));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartObject));
+        assert_eq!(parser.depth(), 2);
+        parser.skip().unwrap();
+        assert_eq!(parser.depth(), 1);
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartObject));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::Key("id")));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::Number(Number::Int(2))));
+    }
+
+    // 快速路径的结果必须和标准库解析逐位一致
+    #[cfg(not(feature = "arbitrary_precision"))]
+    #[test]
+    fn convert_number_matches_std() {
+        let fixed = [
+            "0",
+            "-0",
+            "0.0",
+            "-0.0",
+            "0e10",
+            "1",
+            "-1",
+            "9223372036854775807",
+            "9223372036854775808",
+            "-9223372036854775808",
+            "-9223372036854775809",
+            "18446744073709551615",
+            "18446744073709551616",
+            "-18446744073709551616",
+            "0.1",
+            "0.3",
+            "1.7976931348623157e308",
+            "5e-324",
+            "2.2250738585072014e-308",
+            "9007199254740992",
+            "9007199254740993.0",
+            "9007199254740993e0",
+            "123456789012345678901234567890.0",
+            "1e22",
+            "1e23",
+            "1e37",
+            "3e37",
+            "123e30",
+            "1e-22",
+            "1e-23",
+            "0.000000000000000000000000001",
+            "1.00000000000000000000000000001",
+            "-73.98566",
+            "40.748817",
+            "1E+2",
+            "1e-0",
+        ];
+        for s in fixed {
+            let is_float = s.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'));
+            assert_eq!(
+                format!("{:?}", Parser::convert_number(s.as_bytes(), is_float)),
+                format!("{:?}", Parser::convert_number_slow(s.as_bytes(), is_float)),
+                "{s}"
+            );
+        }
+
+        // 用简单的线性同余生成器构造各种长度的数字
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..100_000 {
+            let mut s = String::new();
+            if next(2) == 0 {
+                s.push('-');
+            }
+            // 一半较短以覆盖快速路径，一半较长以覆盖回退路径
+            let max_len = if next(2) == 0 { 8 } else { 22 };
+            let int_len = 1 + next(max_len);
+            for i in 0..int_len {
+                let d = if i == 0 && int_len > 1 { 1 + next(9) } else { next(10) };
+                s.push((b'0' + d as u8) as char);
+            }
+            if next(2) == 0 {
+                s.push('.');
+                for _ in 0..1 + next(max_len) {
+                    s.push((b'0' + next(10) as u8) as char);
+                }
+            }
+            if next(3) == 0 {
+                s.push('e');
+                match next(3) {
+                    0 => s.push('-'),
+                    1 => s.push('+'),
+                    _ => {}
+                }
+                s.push_str(&next(350).to_string());
+            }
+            let is_float = s.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'));
+            assert_eq!(
+                format!("{:?}", Parser::convert_number(s.as_bytes(), is_float)),
+                format!("{:?}", Parser::convert_number_slow(s.as_bytes(), is_float)),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn find_special_matches_scalar() {
+        let scalar = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+                .unwrap_or(bytes.len())
+        };
+        let mut bytes: Vec<u8> = "abcdefghij中文klmnopqrstuvwxyz0123456789".bytes().collect();
+        assert_eq!(Parser::find_special(&bytes), bytes.len());
+        for special in [b'"', b'\\', 0x00, 0x1f, b'\n'] {
+            for i in 0..bytes.len() {
+                let original = bytes[i];
+                bytes[i] = special;
+                for start in 0..9 {
+                    assert_eq!(Parser::find_special(&bytes[start..]), scalar(&bytes[start..]));
+                }
+                bytes[i] = original;
+            }
+        }
+        // 0x80以上的字节和0x20、0x7f都不是特殊字符
+        assert_eq!(
+            Parser::find_special(&[0x20, 0x7f, 0x80, 0xff, 0x21, 0x5b, 0x5d, 0x23, 0x5c]),
+            8
+        );
+    }
+
+    #[test]
+    fn parse_string_segments() {
+        // 转义和非ASCII字符出现在8字节分组的各个位置，切片和Read输入的结果相同
+        for i in 0..20 {
+            let prefix = "x".repeat(i);
+            let cases = [
+                (format!(r#""{prefix}\n{prefix}""#), format!("{prefix}\n{prefix}")),
+                (
+                    format!(r#""{prefix}中\u4e2d{prefix}""#),
+                    format!("{prefix}中中{prefix}"),
+                ),
+                (format!(r#""{prefix}\ud83d\ude00\"""#), format!("{prefix}😀\"")),
+            ];
+            for (json, expected) in cases {
+                assert_eq!(Value::parse(&json).unwrap(), Value::String(expected.clone()));
+                assert_eq!(Value::from_reader(json.as_bytes()).unwrap(), Value::String(expected));
+            }
+            let json = format!("[\"{prefix}\x01\"]");
+            let e = Value::from_reader(json.as_bytes()).err().unwrap();
+            assert_eq!((e.kind(), e.offset()), (ParseErrorKind::InvalidStringChar, 2 + i));
+            let mut json = format!("[\"{prefix}\\n").into_bytes();
+            json.extend_from_slice(b"\xff\"]");
+            let e = Value::from_reader(&json[..]).err().unwrap();
+            assert_eq!((e.kind(), e.offset()), (ParseErrorKind::InvalidUtf8, 4 + i));
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::error::ParseError;
+use crate::options::ParseOptions;
+use crate::parser::{Event, Parser};
+use crate::value::Value;
+use std::borrow::Cow;
+use std::ops::{Index, Range};
+
+// 一段已验证的JSON文本，保存一个完整的值而不构建Value，输出时原样写回。
+// 同时是按需解析的入口：field、element等方法只扫描文本，找到的子文档仍是RawValue，
+// 只有调用to_value的子树才会被构建。与ValueRef相同是动态大小类型，
+// 以&RawValue或Box<RawValue>的形式使用
+#[repr(transparent)]
+pub struct RawValue {
+    json: str,
+}
+
+// 文本已经验证过，按需解析时不再施加任何限制
+pub(crate) fn unlimited() -> ParseOptions {
+    ParseOptions {
+        max_depth: usize::MAX,
+        max_document_size: usize::MAX,
+        max_string_length: usize::MAX,
+        max_array_length: usize::MAX,
+        max_object_length: usize::MAX,
+        max_nodes: usize::MAX,
+        ..Default::default()
+    }
+}
+
+// 读取下一个完整的值，返回它在输入中的范围，容器会被整个跳过；遇到容器的结束时返回None。
+// 范围的开头可能带有前一个值之后的空白、逗号或冒号
+pub(crate) fn next_span(parser: &mut Parser) -> Result<Option<Range<usize>>, ParseError> {
+    let start = parser.offset();
+    match parser.next_event()? {
+        None | Some(Event::EndArray | Event::EndObject) => return Ok(None),
+        Some(Event::StartArray | Event::StartObject) => parser.skip()?,
+        Some(_) => {}
+    }
+    Ok(Some(start..parser.offset()))
+}
+
+pub(crate) fn trim(json: &str) -> &str {
+    json.trim_start_matches([' ', '\t', '\n', '\r', ',', ':'])
+        .trim_end_matches([' ', '\t', '\n', '\r'])
+}
+
+impl RawValue {
+    fn new(json: &str) -> &RawValue {
+        // SAFETY: RawValue是repr(transparent)的str，两者的布局和指针元数据相同
+        unsafe { &*(json as *const str as *const RawValue) }
+    }
+
+    fn new_boxed(json: Box<str>) -> Box<RawValue> {
+        // SAFETY: 同new
+        unsafe { Box::from_raw(Box::into_raw(json) as *mut RawValue) }
+    }
+
+    // 验证整个文档，返回去掉首尾空白的文本
+    pub fn parse(json: &str) -> Result<&RawValue, ParseError> {
+        RawValue::parse_with_options(json, ParseOptions::default())
+    }
+
+    pub fn parse_with_options(json: &str, options: ParseOptions) -> Result<&RawValue, ParseError> {
+        let mut parser = Parser::new(json.as_bytes()).with_options(options);
+        while parser.next_event()?.is_some() {}
+        Ok(RawValue::new(trim(json)))
+    }
+
+    // 同parse，但取得文本的所有权；文本首尾没有空白时不再复制
+    pub fn from_string(json: String) -> Result<Box<RawValue>, ParseError> {
+        let len = RawValue::parse(&json)?.get().len();
+        if len == json.len() {
+            Ok(RawValue::new_boxed(json.into_boxed_str()))
+        } else {
+            Ok(RawValue::parse(&json)?.to_owned())
+        }
+    }
+
+    // 读取json开头的一个完整的值，同时返回其后剩余的文本；serde的反序列化使用
+    #[cfg(feature = "serde")]
+    pub(crate) fn split_first(json: &str, options: ParseOptions) -> Result<(&RawValue, &str), ParseError> {
+        let mut parser = Parser::new(json.as_bytes()).with_options(options);
+        match next_span(&mut parser)? {
+            Some(span) => Ok((RawValue::new(trim(&json[span.start..span.end])), &json[span.end..])),
+            None => Err(parser.error(crate::error::ParseErrorKind::ExpectValue)),
+        }
+    }
+
+    pub fn get(&self) -> &str {
+        &self.json
+    }
+
+    pub fn is_null(&self) -> bool {
+        &self.json == "null"
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.json.starts_with('[')
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.json.starts_with('{')
+    }
+
+    // 构建这个值的Value；重复的键与Value::parse相同，保留最后一次出现的值。
+    // 不限制深度，嵌套极深的结果要用Value::drop_deep释放
+    pub fn to_value(&self) -> Value {
+        Value::parse_with_options(&self.json, unlimited()).expect("RawValue always holds valid JSON")
+    }
+
+    // 数组的元素；不是数组时为空
+    pub fn elements(&self) -> impl Iterator<Item = &RawValue> {
+        let mut parser = Parser::new(self.json.as_bytes()).with_options(unlimited());
+        let is_array = matches!(parser.next_event(), Ok(Some(Event::StartArray)));
+        std::iter::from_fn(move || {
+            if !is_array {
+                return None;
+            }
+            let span = next_span(&mut parser).expect("RawValue always holds valid JSON")?;
+            Some(RawValue::new(trim(&self.json[span])))
+        })
+    }
+
+    // 对象的成员，按文档顺序，重复的键都会出现；不含转义的键直接借用文本。不是对象时为空
+    pub fn members(&self) -> impl Iterator<Item = (Cow<'_, str>, &RawValue)> {
+        let mut parser = Parser::new(self.json.as_bytes()).with_options(unlimited());
+        let is_object = matches!(parser.next_event(), Ok(Some(Event::StartObject)));
+        std::iter::from_fn(move || {
+            if !is_object || !matches!(parser.next_event(), Ok(Some(Event::Key(_)))) {
+                return None;
+            }
+            let key = parser.borrowed_str();
+            let span = next_span(&mut parser).expect("RawValue always holds valid JSON")?;
+            Some((key, RawValue::new(trim(&self.json[span]))))
+        })
+    }
+
+    // 按键查找对象的成员，只扫描到对象末尾，不构建其他成员；
+    // 键重复时与Value::parse相同，返回最后一次出现的值
+    pub fn field(&self, key: &str) -> Option<&RawValue> {
+        self.members().filter(|(k, _)| k == key).last().map(|(_, v)| v)
+    }
+
+    pub fn element(&self, index: usize) -> Option<&RawValue> {
+        self.elements().nth(index)
+    }
+}
+
+impl ToOwned for RawValue {
+    type Owned = Box<RawValue>;
+
+    fn to_owned(&self) -> Box<RawValue> {
+        RawValue::new_boxed(self.json.into())
+    }
+}
+
+impl Clone for Box<RawValue> {
+    fn clone(&self) -> Self {
+        (**self).to_owned()
+    }
+}
+
+impl Index<&str> for RawValue {
+    type Output = RawValue;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        if !self.is_object() {
+            panic!("Only objects can be accessed by key")
+        }
+        self.field(key).expect("key not found in object")
+    }
+}
+
+impl Index<usize> for RawValue {
+    type Output = RawValue;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if !self.is_array() {
+            panic!("Only arrays can be accessed by index")
+        }
+        self.element(index).expect("index out of bounds")
+    }
+}
+
+impl std::fmt::Display for RawValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.json)
+    }
+}
+
+impl std::fmt::Debug for RawValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RawValue").field(&&self.json).finish()
+    }
+}
+
+impl PartialEq for RawValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.json == other.json
+    }
+}
+
+impl Eq for RawValue {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::Number;
+
+    #[test]
+    fn raw_on_demand() {
+        let json = " {\"id\": 7, \"user\": {\"name\": \"kjson\", \"tags\": [1, [2, 3], {\"k\": null}]},\n \"x\\ty\": true, \"id\": 8} ";
+        let doc = RawValue::parse(json).unwrap();
+        assert_eq!(doc.get(), json.trim());
+        assert!(doc.is_object() && !doc.is_array());
+        assert_eq!(doc.field("id").unwrap().get(), "8");
+        let user = doc.field("user").unwrap();
+        assert_eq!(user.get(), r#"{"name": "kjson", "tags": [1, [2, 3], {"k": null}]}"#);
+        let tags: Vec<&str> = user.field("tags").unwrap().elements().map(RawValue::get).collect();
+        assert_eq!(tags, ["1", "[2, 3]", r#"{"k": null}"#]);
+        assert!(user["tags"].element(2).unwrap().field("k").unwrap().is_null());
+        assert!(user.field("missing").is_none() && user.element(0).is_none());
+        assert!(doc.field("id").unwrap().elements().next().is_none());
+
+        let members: Vec<(Cow<str>, &str)> = doc.members().map(|(k, v)| (k, v.get())).collect();
+        assert_eq!(members.len(), 4);
+        assert!(matches!(members[0].0, Cow::Borrowed("id")));
+        assert!(matches!(&members[2].0, Cow::Owned(k) if k == "x\ty"));
+        assert_eq!(members[2].1, "true");
+
+        assert_eq!(
+            user.field("name").unwrap().to_value(),
+            Value::String("kjson".to_string())
+        );
+        assert_eq!(doc.to_value(), Value::parse(json).unwrap());
+        assert_eq!(doc.field("id").unwrap().to_value(), Value::Number(Number::from(8)));
+    }
+
+    #[test]
+    fn raw_owned() {
+        let raw = RawValue::from_string(" [1, \"a\"]\n".to_string()).unwrap();
+        assert_eq!(raw.get(), r#"[1, "a"]"#);
+        assert_eq!(raw.clone(), raw);
+        assert_eq!(raw.to_string(), r#"[1, "a"]"#);
+        assert_eq!(format!("{raw:?}"), r#"RawValue("[1, \"a\"]")"#);
+        let text = String::from("null");
+        let ptr = text.as_ptr();
+        assert_eq!(RawValue::from_string(text).unwrap().get().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn raw_deep_navigation() {
+        // 查找成员时跳过嵌套极深的子树也不使用递归
+        let json = crate::tests::deep_document(100_000);
+        let options = ParseOptions {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        let mut raw = RawValue::parse_with_options(&json, options).unwrap();
+        for _ in 0..3 {
+            raw = raw.element(0).unwrap().field("a").unwrap();
+        }
+        assert_eq!(raw.get(), &json[3 * 6..json.len() - 3 * 2]);
+    }
+}
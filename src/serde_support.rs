@@ -3,11 +3,22 @@ mod error;
 mod ser;
 
 #[allow(unused)]
-pub use de::from_str;
+pub use de::{from_reader, from_reader_with_options, from_str, from_str_with_options, from_value};
 #[allow(unused)]
 pub use error::JsonError;
 #[allow(unused)]
-pub use ser::to_string;
+pub use ser::{to_string, to_string_with_options};
+
+// Name of the newtype struct that carries the exact text of an arbitrary
+// precision number through the serde data model. Formats that know nothing
+// about it see a plain string.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) const RAW_NUMBER_TOKEN: &str = "$kjson::private::RawNumber";
+
+// Name of the newtype struct that carries the text of a `RawValue`. The
+// Serializer writes the text as is and the Deserializer hands over the exact
+// text of the next value. Other formats see a plain string.
+pub(crate) const RAW_VALUE_TOKEN: &str = "$kjson::private::RawValue";
 
 #[cfg(test)]
 mod tests {
@@ -43,7 +54,416 @@ mod tests {
         let v = Value::parse(json_str).unwrap();
 
         let person1: Person = from_str(&to_string(&v).unwrap()).unwrap();
-        let person2: Person = from_str(&json_str).unwrap();
+        let person2: Person = from_str(json_str).unwrap();
         assert_eq!(person1, person2);
     }
+
+    #[test]
+    fn test_from_reader() {
+        let json_str = r#"{"name":"Alice","age":30,"hobbies":["reading","coding"]}"#;
+        let person: Person = from_reader(json_str.as_bytes()).unwrap();
+        assert_eq!(person, from_str::<Person>(json_str).unwrap());
+
+        match from_reader::<_, Person>(r#"{"name":"Alice""#.as_bytes()) {
+            Err(JsonError::Parse(e)) => assert_eq!(e.kind(), crate::ParseErrorKind::MissCommaOrCurlyBracket),
+            _ => panic!("expected a parse error"),
+        }
+        match from_reader::<_, Person>(format!("{json_str} 1").as_bytes()) {
+            Err(JsonError::Parse(e)) => assert_eq!(e.kind(), crate::ParseErrorKind::RootNotSingular),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_from_reader_streaming() {
+        use crate::RawValue;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum E {
+            Unit,
+            Newtype(Option<u32>),
+            Tuple(i8, String),
+            Struct { a: Vec<u32> },
+        }
+        #[derive(Deserialize, Debug)]
+        struct Doc {
+            items: Vec<E>,
+            pair: (bool, f64),
+            note: Option<String>,
+            raw: Box<RawValue>,
+        }
+
+        let json = r#"{"items": ["Unit", {"Newtype": null}, {"Newtype": 7}, {"Tuple": [-1, "a\"b"]},
+            {"Struct": {"a": [1, 2]}}], "ignored": {"x": [1, {}]}, "pair": [true, 2.5], "note": null,
+            "raw": {"k": [1, "s"]}}"#;
+        let doc: Doc = from_reader(json.as_bytes()).unwrap();
+        let items = [
+            E::Unit,
+            E::Newtype(None),
+            E::Newtype(Some(7)),
+            E::Tuple(-1, "a\"b".into()),
+            E::Struct { a: vec![1, 2] },
+        ];
+        assert_eq!(doc.items, items);
+        assert_eq!((doc.pair, doc.note), ((true, 2.5), None));
+        assert_eq!(doc.raw.get(), r#"{"k": [1, "s"]}"#);
+        assert_eq!(
+            from_reader::<_, Value>(json.as_bytes()).unwrap(),
+            Value::parse(json).unwrap()
+        );
+
+        assert_eq!(from_str::<Value>("[null]").unwrap(), Value::parse("[null]").unwrap());
+        assert!(from_reader::<_, u8>("256".as_bytes()).is_err());
+        assert!(matches!(
+            from_reader::<_, (u8,)>("[1, 2]".as_bytes()),
+            Err(JsonError::ExpectedArrayEnd)
+        ));
+        assert!(matches!(
+            from_reader::<_, E>("[]".as_bytes()),
+            Err(JsonError::ExpectedEnum)
+        ));
+
+        let duplicates = r#"{"x":1,"tag":"a","x":2}"#;
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Point {
+            x: u32,
+            tag: String,
+        }
+        let options = |policy| crate::ParseOptions {
+            duplicate_keys: policy,
+            ..Default::default()
+        };
+        let read = |policy| from_reader_with_options::<_, Point>(duplicates.as_bytes(), options(policy));
+        assert!(matches!(read(crate::DuplicateKeys::Error), Err(JsonError::DuplicateKey(key)) if key == "x"));
+        assert_eq!(read(crate::DuplicateKeys::FirstWins).unwrap().x, 1);
+        assert_eq!(read(crate::DuplicateKeys::LastWins).unwrap().x, 2);
+        assert!(matches!(
+            from_reader::<_, Point>(duplicates.as_bytes()),
+            Err(JsonError::Message(_))
+        ));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let deep = "[".repeat(100_000);
+        assert!(matches!(from_str::<Value>(&deep), Err(JsonError::DepthLimitExceeded)));
+        match from_reader::<_, Value>(deep.as_bytes()) {
+            Err(JsonError::Parse(e)) => assert_eq!(e.kind(), crate::ParseErrorKind::DepthLimitExceeded),
+            _ => panic!("expected a parse error"),
+        }
+
+        let options = crate::ParseOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let v: Vec<Vec<u32>> = from_str_with_options("[[1],[2,3]]", options.clone()).unwrap();
+        assert_eq!(v, vec![vec![1], vec![2, 3]]);
+        assert!(matches!(
+            from_str_with_options::<Vec<Vec<Vec<u32>>>>("[[[1]]]", options),
+            Err(JsonError::DepthLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = crate::ParseOptions {
+            max_document_size: 32,
+            max_string_length: 4,
+            max_array_length: 3,
+            max_object_length: 2,
+            max_nodes: 6,
+            ..Default::default()
+        };
+        let parse = |json: &str| from_str_with_options::<Value>(json, limits.clone());
+        assert!(parse(r#"[1,"abcd",{"a":2}]"#).is_ok());
+        assert!(matches!(parse(&" ".repeat(33)), Err(JsonError::DocumentTooLarge)));
+        assert!(matches!(parse(r#"["abcde"]"#), Err(JsonError::StringTooLong)));
+        assert!(matches!(parse("[1,2,3,4]"), Err(JsonError::ArrayTooLong)));
+        assert!(matches!(parse(r#"{"a":1,"b":2,"c":3}"#), Err(JsonError::ObjectTooLong)));
+        assert!(matches!(parse("[[1,2],[3,4]]"), Err(JsonError::TooManyNodes)));
+
+        match from_reader_with_options::<_, Value>("[1,2,3,4]".as_bytes(), limits) {
+            Err(JsonError::Parse(e)) => assert_eq!(e.kind(), crate::ParseErrorKind::ArrayTooLong),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Point<'a> {
+            x: u32,
+            tag: &'a str,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Owned {
+            x: u32,
+            tag: String,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Points {
+            x: Vec<u32>,
+        }
+        let json = r#"{"x":1,"tag":"a","x":2}"#;
+        let options = |policy| crate::ParseOptions {
+            duplicate_keys: policy,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            from_str_with_options::<Point>(json, options(crate::DuplicateKeys::Error)),
+            Err(JsonError::DuplicateKey(key)) if key == "x"
+        ));
+        let point: Point = from_str_with_options(json, options(crate::DuplicateKeys::FirstWins)).unwrap();
+        assert_eq!(point, Point { x: 1, tag: "a" });
+
+        // 没有重复键时仍可借用输入中的字符串
+        let point: Point =
+            from_str_with_options(r#"{"x":1,"tag":"a"}"#, options(crate::DuplicateKeys::LastWins)).unwrap();
+        assert_eq!(point, Point { x: 1, tag: "a" });
+        let owned: Owned = from_str_with_options(json, options(crate::DuplicateKeys::LastWins)).unwrap();
+        assert_eq!(owned, Owned { x: 2, tag: "a".into() });
+        let points: Points = from_str_with_options(json, options(crate::DuplicateKeys::CollectAll)).unwrap();
+        assert_eq!(points, Points { x: vec![1, 2] });
+
+        // from_str交给Deserialize实现处理：派生的结构体拒绝重复字段
+        assert!(matches!(from_str::<Point>(json), Err(JsonError::Message(_))));
+    }
+
+    #[test]
+    fn test_from_value() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum E {
+            Unit,
+            Newtype(Option<u32>),
+            Struct { a: Vec<u32> },
+        }
+
+        let v = Value::parse(r#"["Unit", {"Newtype": null}, {"Struct": {"a": [1, 2]}}]"#).unwrap();
+        let e: Vec<E> = from_value(v).unwrap();
+        assert_eq!(e, vec![E::Unit, E::Newtype(None), E::Struct { a: vec![1, 2] }]);
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn test_arbitrary_precision() {
+        use crate::Number;
+
+        // 一个只接受精确十进制文本的类型
+        #[derive(PartialEq, Debug)]
+        struct Decimal(String);
+        impl<'de> Deserialize<'de> for Decimal {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Number::deserialize(deserializer).map(|n| Decimal(n.to_string()))
+            }
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Price {
+            amount: Decimal,
+            count: u32,
+        }
+
+        let json = r#"{"amount":0.10000000000000000000001,"count":3}"#;
+        let expected = Price {
+            amount: Decimal("0.10000000000000000000001".to_string()),
+            count: 3,
+        };
+        assert_eq!(from_str::<Price>(json).unwrap(), expected);
+        assert_eq!(from_value::<Price>(Value::parse(json).unwrap()).unwrap(), expected);
+        assert!(from_str::<Price>(r#"{"amount":1.e5,"count":3}"#).is_err());
+
+        let array = "[0.10000000000000000000001,1e400,-0,1E+2]";
+        assert_eq!(to_string(&Value::parse(array).unwrap()).unwrap(), array);
+        assert_eq!(from_value::<f64>(Value::parse("1.5").unwrap()).unwrap(), 1.5);
+        assert_eq!(
+            from_value::<u128>(Value::parse(&10u128.pow(30).to_string()).unwrap()).unwrap(),
+            10u128.pow(30)
+        );
+        // 带指数的文本是浮点数，与未启用arbitrary_precision时相同
+        assert!(from_value::<u128>(Value::parse("1e30").unwrap()).is_err());
+        assert!(from_value::<f64>(Value::parse("1e400").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_float_format() {
+        assert_eq!(to_string(&(1.0f64, 0.1f32, 1e300, -0.0)).unwrap(), "[1,0.1,1e300,-0]");
+        assert_eq!(to_string(&[f64::NAN, f64::NEG_INFINITY]).unwrap(), "[null,null]");
+        let options = crate::WriteOptions {
+            non_finite_floats: crate::NonFiniteFloats::Error,
+            float_suffix: true,
+        };
+        assert_eq!(
+            to_string_with_options(&[2.0, 2.5], options.clone()).unwrap(),
+            "[2.0,2.5]"
+        );
+        assert!(matches!(
+            to_string_with_options(&f64::NAN, options),
+            Err(JsonError::Write(crate::WriteError::NonFiniteFloat))
+        ));
+    }
+
+    #[test]
+    fn test_big_integers() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Big {
+            u: u64,
+            i: i128,
+            w: u128,
+        }
+        let big = Big {
+            u: u64::MAX,
+            i: i128::MIN,
+            w: u128::MAX,
+        };
+        let json = to_string(&big).unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"u":{},"i":{},"w":{}}}"#, u64::MAX, i128::MIN, u128::MAX)
+        );
+        assert_eq!(from_value::<Big>(Value::parse(&json).unwrap()).unwrap(), big);
+        assert_eq!(from_str::<u128>(&u128::MAX.to_string()).unwrap(), u128::MAX);
+        let array = format!("[{},{},{}]", u64::MAX, i128::MIN, u128::MAX);
+        assert_eq!(to_string(&Value::parse(&array).unwrap()).unwrap(), array);
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(from_str::<i64>("-5").unwrap(), -5);
+        assert_eq!(from_str::<i8>("-128").unwrap(), i8::MIN);
+        assert_eq!(from_str::<i128>(&i128::MIN.to_string()).unwrap(), i128::MIN);
+        assert_eq!(from_str::<u128>(&u128::MAX.to_string()).unwrap(), u128::MAX);
+        assert_eq!(from_str::<Value>("-1").unwrap(), Value::parse("-1").unwrap());
+        assert_eq!(
+            from_str::<Value>("[1.5,-2e3]").unwrap(),
+            Value::parse("[1.5,-2e3]").unwrap()
+        );
+        assert_eq!(from_str::<f64>("-2.5e-3").unwrap(), -2.5e-3);
+        assert_eq!(from_str::<f32>("1").unwrap(), 1.0);
+        assert_eq!(from_str::<char>(r#""x""#).unwrap(), 'x');
+        assert!(from_str::<char>(r#""xy""#).is_err());
+
+        // Out of range integers are errors instead of panics or wrapped values.
+        assert!(matches!(from_str::<u8>("256"), Err(JsonError::NumberOutOfRange)));
+        assert!(matches!(from_str::<u32>("-1"), Err(JsonError::NumberOutOfRange)));
+        assert!(matches!(from_str::<i8>("-129"), Err(JsonError::NumberOutOfRange)));
+        let over = u128::MAX.to_string() + "0";
+        assert!(matches!(from_str::<u128>(&over), Err(JsonError::NumberOutOfRange)));
+        assert!(matches!(
+            from_str::<i128>(&format!("-{over}")),
+            Err(JsonError::NumberOutOfRange)
+        ));
+        assert!(matches!(from_str::<i64>("1.5"), Err(JsonError::ExpectedInteger)));
+        assert!(matches!(from_str::<u64>("01"), Err(JsonError::Syntax)));
+        assert!(matches!(from_str::<f64>("x"), Err(JsonError::ExpectedNumber)));
+    }
+
+    #[test]
+    fn test_raw_value() {
+        use crate::RawValue;
+
+        #[derive(Serialize, Deserialize, Debug)]
+        struct Envelope {
+            kind: String,
+            payload: Box<RawValue>,
+            extra: Vec<Box<RawValue>>,
+        }
+        #[derive(Deserialize, Debug)]
+        struct Borrowed<'a> {
+            kind: &'a str,
+            #[serde(borrow)]
+            payload: &'a RawValue,
+            #[serde(borrow)]
+            extra: Vec<&'a RawValue>,
+        }
+
+        let payload = r#"{"a": [1, 2.5, "x"],  "c": {"d": null}}"#;
+        let json = format!(r#"{{"kind":"event","payload":{payload},"extra":[true,[ ],"s"]}}"#);
+        let envelope: Envelope = from_str(&json).unwrap();
+        assert_eq!(envelope.payload.get(), payload);
+        let extra: Vec<&str> = envelope.extra.iter().map(|raw| raw.get()).collect();
+        assert_eq!(extra, ["true", "[ ]", r#""s""#]);
+        assert_eq!(
+            envelope.payload.field("c").unwrap().to_value(),
+            Value::parse(r#"{"d": null}"#).unwrap()
+        );
+        // 序列化时原样写回
+        assert_eq!(to_string(&envelope).unwrap(), json);
+
+        let borrowed: Borrowed = from_str(&json).unwrap();
+        assert_eq!((borrowed.kind, borrowed.payload.get()), ("event", payload));
+        assert_eq!(borrowed.extra[1].get(), "[ ]");
+        assert_eq!(
+            borrowed.payload.get().as_ptr(),
+            json[json.find(payload).unwrap()..].as_ptr()
+        );
+
+        // 从Value反序列化时重新生成文本
+        let envelope: Envelope = from_value(Value::parse(&json).unwrap()).unwrap();
+        assert_eq!(envelope.payload.to_value(), Value::parse(payload).unwrap());
+        assert_eq!(envelope.extra[1].get(), "[]");
+
+        assert!(matches!(
+            from_str::<Envelope>(r#"{"kind":"e","payload":[1,,"extra":[]}"#),
+            Err(JsonError::Parse(e)) if e.kind() == crate::ParseErrorKind::InvalidValue
+        ));
+        let options = crate::ParseOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(from_str_with_options::<Envelope>(r#"{"kind":"e","payload":[[1]],"extra":[]}"#, options).is_err());
+
+        // 从reader读取或按重复键策略解析时同样保留原文
+        let envelope: Envelope = from_reader(json.as_bytes()).unwrap();
+        assert_eq!((envelope.payload.get(), envelope.extra[1].get()), (payload, "[ ]"));
+        let big = format!("[{}1.0]", r#"{"b": 1.0, "a": "x"}, "#.repeat(10_000));
+        let envelope: Envelope =
+            from_reader(format!(r#"{{"kind":"","payload":{big},"extra":[]}}"#).as_bytes()).unwrap();
+        assert_eq!(envelope.payload.get(), big);
+
+        let duplicates = format!(r#"{{"kind":"a","payload":{payload},"extra":[1.0],"kind":"b"}}"#);
+        let options = |policy| crate::ParseOptions {
+            duplicate_keys: policy,
+            ..Default::default()
+        };
+        let last_wins = options(crate::DuplicateKeys::LastWins);
+        let envelope: Envelope = from_str_with_options(&duplicates, last_wins.clone()).unwrap();
+        assert_eq!((envelope.kind.as_str(), envelope.payload.get()), ("b", payload));
+        assert_eq!(envelope.extra[0].get(), "1.0");
+        let envelope: Envelope = from_reader_with_options(duplicates.as_bytes(), last_wins).unwrap();
+        assert_eq!((envelope.kind.as_str(), envelope.payload.get()), ("b", payload));
+
+        #[derive(Deserialize, Debug)]
+        struct Collected {
+            r: Vec<Box<RawValue>>,
+        }
+        let json = r#"{"r": {"b": 1.0}, "x": 0, "r" : [ 1e1 ]}"#;
+        let collect_all = options(crate::DuplicateKeys::CollectAll);
+        let collected: Collected = from_str_with_options(json, collect_all.clone()).unwrap();
+        let texts: Vec<&str> = collected.r.iter().map(|raw| raw.get()).collect();
+        assert_eq!(texts, [r#"{"b": 1.0}"#, "[ 1e1 ]"]);
+        let collected: Collected = from_reader_with_options(json.as_bytes(), collect_all).unwrap();
+        let texts: Vec<&str> = collected.r.iter().map(|raw| raw.get()).collect();
+        assert_eq!(texts, [r#"{"b": 1.0}"#, "[ 1e1 ]"]);
+
+        // 原始文本中的引号和反斜杠原样写出，普通字符串照常转义
+        #[derive(Serialize)]
+        struct Mixed<'a> {
+            raw: &'a RawValue,
+            text: String,
+        }
+        let raw = RawValue::parse(r#"{"q": "say \"hi\"", "p": "C:\\dir"}"#).unwrap();
+        let mixed = Mixed {
+            raw,
+            text: r#"a "quoted" \ text"#.to_string(),
+        };
+        let json = to_string(&mixed).unwrap();
+        assert_eq!(
+            json,
+            r#"{"raw":{"q": "say \"hi\"", "p": "C:\\dir"},"text":"a \"quoted\" \\ text"}"#
+        );
+        let v = Value::parse(&json).unwrap();
+        assert_eq!(v["raw"], raw.to_value());
+        assert_eq!(v["text"], Value::String(mixed.text));
+        assert_eq!(to_string(&"a\"b").unwrap(), r#""a\"b""#);
+    }
 }
@@ -1,14 +1,33 @@
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParseOptions};
+use crate::parser::{Event, Parser};
+use crate::raw::{RawValue, next_span, trim, unlimited};
+#[cfg(feature = "arbitrary_precision")]
+use crate::serde_support::RAW_NUMBER_TOKEN;
+use crate::serde_support::RAW_VALUE_TOKEN;
 use crate::serde_support::error::JsonError;
 use crate::{dict::Dict, value::Value};
 use serde::Deserialize;
-use serde::de::{self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqAccessDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::ops::{AddAssign, MulAssign, Neg};
+use std::io::Read;
 
 pub struct Deserializer<'de> {
     // This string starts with the input data and characters are truncated off
     // the beginning as data is parsed.
     input: &'de str,
+    // How many more arrays, maps or enum variants may be entered before the
+    // input is rejected. This keeps hostile input from overflowing the stack.
+    remaining_depth: usize,
+    // Number of values started so far, checked against `options.max_nodes`.
+    nodes: usize,
+    options: ParseOptions,
+    // Whether map keys are checked against `options.duplicate_keys`.
+    check_duplicates: bool,
 }
 
 impl<'de> Deserializer<'de> {
@@ -17,7 +36,17 @@ impl<'de> Deserializer<'de> {
     // `serde_json::from_str(...)` while advanced use cases that require a
     // deserializer can make one with `serde_json::Deserializer::from_str(...)`.
     pub fn from_str(input: &'de str) -> Self {
-        Deserializer { input }
+        Deserializer::from_str_with_options(input, ParseOptions::default())
+    }
+
+    pub fn from_str_with_options(input: &'de str, options: ParseOptions) -> Self {
+        Deserializer {
+            input,
+            remaining_depth: options.max_depth,
+            nodes: 0,
+            options,
+            check_duplicates: false,
+        }
     }
 }
 
@@ -25,12 +54,48 @@ impl<'de> Deserializer<'de> {
 // `from_xyz` methods such as `from_str`, `from_bytes`, or `from_reader`
 // depending on what Rust types the deserializer is able to consume as input.
 //
-// This basic deserializer supports only `from_str`.
+// This basic deserializer supports `from_str` directly. `from_reader` pulls
+// events from a `Parser` reading the input as it goes.
 pub fn from_str<'a, T>(s: &'a str) -> Result<T, JsonError>
 where
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_str(s);
+    from_text(s, ParseOptions::default(), false)
+}
+
+// Unlike `from_str`, which leaves repeated keys to the `Deserialize` impl
+// (derived structs reject them, maps keep the last value), this honours
+// `options.duplicate_keys`. `Error` and `FirstWins` are decided while reading
+// the text. `LastWins` must see the whole object first, so once a repeated key
+// shows up the text is read again by a `Parser` that looks ahead in every
+// object. `CollectAll` changes the shape of repeated values and always takes
+// that route. In both cases `T` cannot borrow strings from `s`, but a
+// `Box<RawValue>` still gets the exact text of its value.
+pub fn from_str_with_options<'a, T>(s: &'a str, options: ParseOptions) -> Result<T, JsonError>
+where
+    T: Deserialize<'a>,
+{
+    let via_events = |options| from_events(Parser::new(s.as_bytes()).with_options(options), true);
+    match options.duplicate_keys {
+        DuplicateKeys::CollectAll => via_events(options),
+        DuplicateKeys::LastWins => match from_text(s, options.clone(), true) {
+            Err(JsonError::DuplicateKey(_)) => via_events(options),
+            result => result,
+        },
+        DuplicateKeys::Error | DuplicateKeys::FirstWins => from_text(s, options, true),
+    }
+}
+
+fn from_text<'a, T>(s: &'a str, options: ParseOptions, check_duplicates: bool) -> Result<T, JsonError>
+where
+    T: Deserialize<'a>,
+{
+    if s.len() > options.max_document_size {
+        return Err(JsonError::DocumentTooLarge);
+    }
+    let mut deserializer = Deserializer::from_str_with_options(s, options);
+    deserializer.check_duplicates = check_duplicates;
+    deserializer.count_node()?;
     let t = T::deserialize(&mut deserializer)?;
     if deserializer.input.is_empty() {
         Ok(t)
@@ -39,6 +104,52 @@ where
     }
 }
 
+// Like `from_str`, repeated keys are left to the `Deserialize` impl.
+pub fn from_reader<R, T>(reader: R) -> Result<T, JsonError>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    from_events(Parser::from_reader(reader), false)
+}
+
+// Honours `options.duplicate_keys` like `from_str_with_options`. `Error` and
+// `FirstWins` are decided while reading. `LastWins` and `CollectAll` have to
+// look ahead in every object and a reader cannot be read a second time, so for
+// those the whole input is read into memory first. It is still deserialized
+// from the text, not from a `Value`.
+pub fn from_reader_with_options<R, T>(reader: R, options: ParseOptions) -> Result<T, JsonError>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut parser = Parser::from_reader(reader).with_options(options);
+    if matches!(
+        parser.options().duplicate_keys,
+        DuplicateKeys::LastWins | DuplicateKeys::CollectAll
+    ) {
+        parser.read_to_end().map_err(JsonError::Parse)?;
+    }
+    from_events(parser, true)
+}
+
+fn from_events<'de, T>(parser: Parser, check_duplicates: bool) -> Result<T, JsonError>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = EventDeserializer::new(parser, check_duplicates);
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.end_document()?;
+    Ok(t)
+}
+
+pub fn from_value<T>(value: Value) -> Result<T, JsonError>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(value)
+}
+
 // SERDE IS NOT A PARSING LIBRARY. This impl block defines a few basic parsing
 // functions from scratch. More complicated formats may wish to use a dedicated
 // parsing library to help implement their Serde deserializer.
@@ -68,43 +179,89 @@ impl<'de> Deserializer<'de> {
         }
     }
 
-    // Parse a group of decimal digits as an unsigned integer of type T.
-    //
-    // This implementation is a bit too lenient, for example `001` is not
-    // allowed in JSON. Also the various arithmetic operations can overflow and
-    // panic or return bogus data. But it is good enough for example code!
+    // Parse the number at the start of the input. The literal is checked
+    // against the JSON grammar, so `001` or `1.` are rejected.
+    fn parse_number(&mut self) -> Result<(Number, &'de str), JsonError> {
+        let len = self
+            .input
+            .find(|ch: char| !matches!(ch, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(self.input.len());
+        if len == 0 {
+            return Err(JsonError::ExpectedNumber);
+        }
+        let literal = &self.input[..len];
+        let number = Number::from_literal(literal).ok_or(JsonError::Syntax)?;
+        self.input = &self.input[len..];
+        Ok((number, literal))
+    }
+
+    // Parse an integer that must fit in T. Fractions and exponents are
+    // rejected, integers outside the range of T are errors rather than
+    // wrapping around.
     fn parse_unsigned<T>(&mut self) -> Result<T, JsonError>
     where
-        T: AddAssign<T> + MulAssign<T> + From<u8>,
+        T: TryFrom<u128>,
     {
-        let mut int = match self.next_char()? {
-            ch @ '0'..='9' => T::from(ch as u8 - b'0'),
-            _ => {
-                return Err(JsonError::ExpectedInteger);
-            }
-        };
-        loop {
-            match self.input.chars().next() {
-                Some(ch @ '0'..='9') => {
-                    self.input = &self.input[1..];
-                    int *= T::from(10);
-                    int += T::from(ch as u8 - b'0');
-                }
-                _ => {
-                    return Ok(int);
-                }
-            }
+        let (number, literal) = self.parse_number()?;
+        if literal.contains(['.', 'e', 'E']) {
+            return Err(JsonError::ExpectedInteger);
         }
+        number
+            .as_u128()
+            .and_then(|n| T::try_from(n).ok())
+            .ok_or(JsonError::NumberOutOfRange)
     }
 
-    // Parse a possible minus sign followed by a group of decimal digits as a
-    // signed integer of type T.
+    // Same as `parse_unsigned` for a possibly negative integer.
     fn parse_signed<T>(&mut self) -> Result<T, JsonError>
     where
-        T: Neg<Output = T> + AddAssign<T> + MulAssign<T> + From<i8>,
+        T: TryFrom<i128>,
     {
-        // Optional minus sign, delegate to `parse_unsigned`, negate if negative.
-        unimplemented!()
+        let (number, literal) = self.parse_number()?;
+        if literal.contains(['.', 'e', 'E']) {
+            return Err(JsonError::ExpectedInteger);
+        }
+        number
+            .as_i128()
+            .and_then(|n| T::try_from(n).ok())
+            .ok_or(JsonError::NumberOutOfRange)
+    }
+
+    // Parse any number as the nearest f64.
+    fn parse_float(&mut self) -> Result<f64, JsonError> {
+        let (number, _) = self.parse_number()?;
+        number.as_f64().ok_or(JsonError::NumberOutOfRange)
+    }
+
+    // Called before descending into an array, map or enum variant. Every
+    // successful call must be paired with a call to `leave`.
+    fn enter(&mut self) -> Result<(), JsonError> {
+        match self.remaining_depth.checked_sub(1) {
+            Some(depth) => {
+                self.remaining_depth = depth;
+                Ok(())
+            }
+            None => Err(JsonError::DepthLimitExceeded),
+        }
+    }
+
+    fn leave(&mut self) {
+        self.remaining_depth += 1;
+    }
+
+    // Called before every value so that `max_nodes` bounds the total work.
+    fn count_node(&mut self) -> Result<(), JsonError> {
+        if self.nodes >= self.options.max_nodes {
+            return Err(JsonError::TooManyNodes);
+        }
+        self.nodes += 1;
+        Ok(())
+    }
+
+    // The raw text of the string at the start of the input, if any.
+    fn peek_string(&self) -> Option<&'de str> {
+        let rest = self.input.strip_prefix('"')?;
+        rest.find('"').map(|len| &rest[..len])
     }
 
     // Parse a string until the next '"' character.
@@ -116,6 +273,7 @@ impl<'de> Deserializer<'de> {
             return Err(JsonError::ExpectedString);
         }
         match self.input.find('"') {
+            Some(len) if len > self.options.max_string_length => Err(JsonError::StringTooLong),
             Some(len) => {
                 let s = &self.input[..len];
                 self.input = &self.input[len + 1..];
@@ -126,7 +284,7 @@ impl<'de> Deserializer<'de> {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = JsonError;
 
     // Look at the input data to decide what Serde data model type to
@@ -140,8 +298,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             'n' => self.deserialize_unit(visitor),
             't' | 'f' => self.deserialize_bool(visitor),
             '"' => self.deserialize_str(visitor),
-            '0'..='9' => self.deserialize_u64(visitor),
-            '-' => self.deserialize_i64(visitor),
+            '-' | '0'..='9' => {
+                let (number, _) = self.parse_number()?;
+                visit_number(&number, visitor)
+            }
             '[' => self.deserialize_seq(visitor),
             '{' => self.deserialize_map(visitor),
             _ => Err(JsonError::Syntax),
@@ -199,6 +359,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_i64(self.parse_signed()?)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.parse_signed()?)
+    }
+
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
@@ -227,30 +394,40 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_u64(self.parse_unsigned()?)
     }
 
-    // Float parsing is stupidly hard.
-    fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        visitor.visit_u128(self.parse_unsigned()?)
     }
 
-    // Float parsing is stupidly hard.
-    fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    // Float parsing is stupidly hard, so it is left to `Number`.
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        visitor.visit_f32(self.parse_float()? as f32)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(self.parse_float()?)
     }
 
     // The `Serializer` implementation on the previous page serialized chars as
     // single-character strings so handle that representation here.
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        // Parse a string, check that it is one character, call `visit_char`.
-        unimplemented!()
+        let s = self.parse_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => visitor.visit_char(ch),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Str(s), &"a single character")),
+        }
     }
 
     // Refer to the "Understanding deserializer lifetimes" page for information
@@ -271,18 +448,18 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
     // The `Serializer` implementation on the previous page serialized byte
     // arrays as JSON arrays of bytes. Handle that representation here.
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_seq(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_seq(visitor)
     }
 
     // An absent optional is represented as the JSON `null` and a present
@@ -329,10 +506,35 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // As is done here, serializers are encouraged to treat newtype structs as
     // insignificant wrappers around the data they contain. That means not
     // parsing anything other than the contained value.
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
+        // `Number` asks for the exact text of the number.
+        #[cfg(feature = "arbitrary_precision")]
+        if name == RAW_NUMBER_TOKEN && matches!(self.peek_char()?, '-' | '0'..='9') {
+            let len = self
+                .input
+                .find(|ch: char| !matches!(ch, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                .unwrap_or(self.input.len());
+            let literal = &self.input[..len];
+            if Number::from_literal(literal).is_none() {
+                return Err(JsonError::Syntax);
+            }
+            self.input = &self.input[len..];
+            return visitor.visit_borrowed_str(literal);
+        }
+        // `RawValue` asks for the exact text of the next value, which is
+        // validated here without being deserialized.
+        if name == RAW_VALUE_TOKEN {
+            let options = ParseOptions {
+                max_depth: self.remaining_depth,
+                ..self.options.clone()
+            };
+            let (raw, rest) = RawValue::split_first(self.input, options).map_err(JsonError::Parse)?;
+            self.input = rest;
+            return visitor.visit_borrowed_str(raw.get());
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -345,8 +547,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         // Parse the opening bracket of the sequence.
         if self.next_char()? == '[' {
+            self.enter()?;
             // Give the visitor access to each element of the sequence.
             let value = visitor.visit_seq(CommaSeparated::new(self))?;
+            self.leave();
             // Parse the closing bracket of the sequence.
             if self.next_char()? == ']' {
                 Ok(value)
@@ -388,8 +592,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         // Parse the opening brace of the map.
         if self.next_char()? == '{' {
+            self.enter()?;
             // Give the visitor access to each entry of the map.
             let value = visitor.visit_map(CommaSeparated::new(self))?;
+            self.leave();
             // Parse the closing brace of the map.
             if self.next_char()? == '}' {
                 Ok(value)
@@ -432,8 +638,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             // Visit a unit variant.
             visitor.visit_enum(self.parse_string()?.into_deserializer())
         } else if self.next_char()? == '{' {
+            self.enter()?;
             // Visit a newtype variant, tuple variant, or struct variant.
             let value = visitor.visit_enum(Enum::new(self))?;
+            self.leave();
             // Parse the matching close brace.
             if self.next_char()? == '}' {
                 Ok(value)
@@ -481,11 +689,20 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 struct CommaSeparated<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     first: bool,
+    // Elements or entries seen so far, checked against the length limits.
+    count: usize,
+    // Keys seen so far in a map, only recorded when duplicates are checked.
+    keys: HashSet<&'de str>,
 }
 
 impl<'a, 'de> CommaSeparated<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>) -> Self {
-        CommaSeparated { de, first: true }
+        CommaSeparated {
+            de,
+            first: true,
+            count: 0,
+            keys: HashSet::new(),
+        }
     }
 }
 
@@ -507,6 +724,11 @@ impl<'de, 'a> SeqAccess<'de> for CommaSeparated<'a, 'de> {
             return Err(JsonError::ExpectedArrayComma);
         }
         self.first = false;
+        if self.count >= self.de.options.max_array_length {
+            return Err(JsonError::ArrayTooLong);
+        }
+        self.count += 1;
+        self.de.count_node()?;
         // Deserialize an array element.
         seed.deserialize(&mut *self.de).map(Some)
     }
@@ -521,17 +743,37 @@ impl<'de, 'a> MapAccess<'de> for CommaSeparated<'a, 'de> {
     where
         K: DeserializeSeed<'de>,
     {
-        // Check if there are no more entries.
-        if self.de.peek_char()? == '}' {
-            return Ok(None);
-        }
-        // Comma is required before every entry except the first.
-        if !self.first && self.de.next_char()? != ',' {
-            return Err(JsonError::ExpectedMapComma);
+        loop {
+            // Check if there are no more entries.
+            if self.de.peek_char()? == '}' {
+                return Ok(None);
+            }
+            // Comma is required before every entry except the first.
+            if !self.first && self.de.next_char()? != ',' {
+                return Err(JsonError::ExpectedMapComma);
+            }
+            self.first = false;
+            if self.count >= self.de.options.max_object_length {
+                return Err(JsonError::ObjectTooLong);
+            }
+            self.count += 1;
+            if self.de.check_duplicates
+                && let Some(key) = self.de.peek_string()
+                && !self.keys.insert(key)
+            {
+                // `from_str_with_options` falls back to a `Value` for the
+                // policies that cannot be applied while streaming.
+                if self.de.options.duplicate_keys != DuplicateKeys::FirstWins {
+                    return Err(JsonError::DuplicateKey(key.to_string()));
+                }
+                // Skip the whole entry and look at the next one.
+                self.de.parse_string()?;
+                self.next_value::<de::IgnoredAny>()?;
+                continue;
+            }
+            // Deserialize a map key.
+            return seed.deserialize(&mut *self.de).map(Some);
         }
-        self.first = false;
-        // Deserialize a map key.
-        seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
@@ -544,6 +786,7 @@ impl<'de, 'a> MapAccess<'de> for CommaSeparated<'a, 'de> {
         if self.de.next_char()? != ':' {
             return Err(JsonError::ExpectedMapColon);
         }
+        self.de.count_node()?;
         // Deserialize a map value.
         seed.deserialize(&mut *self.de)
     }
@@ -624,6 +867,396 @@ impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
     }
 }
 
+// The Deserializer behind `from_reader`. It pulls events from a `Parser`
+// instead of slicing a string, so the input is never held in memory as a
+// whole. Syntax and the limits in `ParseOptions` are checked by the parser.
+struct EventDeserializer<'a> {
+    parser: Parser<'a>,
+    // The event read ahead of the visitor, if any.
+    peeked: Option<Peeked>,
+    // Whether map keys are checked against `options.duplicate_keys`.
+    check_duplicates: bool,
+}
+
+// An event that has been read but not handed to a visitor yet. The text of a
+// `String` or `Key` stays in the parser until the next event is read.
+enum Peeked {
+    StartObject,
+    Key,
+    EndObject,
+    StartArray,
+    EndArray,
+    String,
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+impl<'a> EventDeserializer<'a> {
+    fn new(parser: Parser<'a>, check_duplicates: bool) -> Self {
+        EventDeserializer {
+            parser,
+            peeked: None,
+            check_duplicates,
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Peeked, JsonError> {
+        let peeked = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => {
+                // Record the text from here on in case the value turns out to
+                // be a `RawValue`.
+                self.parser.start_capture();
+                match self.parser.next_event().map_err(JsonError::Parse)? {
+                    Some(Event::StartObject) => Peeked::StartObject,
+                    Some(Event::Key(_)) => Peeked::Key,
+                    Some(Event::EndObject) => Peeked::EndObject,
+                    Some(Event::StartArray) => Peeked::StartArray,
+                    Some(Event::EndArray) => Peeked::EndArray,
+                    Some(Event::String(_)) => Peeked::String,
+                    Some(Event::Number(n)) => Peeked::Number(n),
+                    Some(Event::Bool(b)) => Peeked::Bool(b),
+                    Some(Event::Null) => Peeked::Null,
+                    None => return Err(JsonError::Eof),
+                }
+            }
+        };
+        Ok(self.peeked.insert(peeked))
+    }
+
+    fn next(&mut self) -> Result<Peeked, JsonError> {
+        self.peek()?;
+        self.peeked.take().ok_or(JsonError::Eof)
+    }
+
+    // Skip the next value, including everything inside an array or object.
+    fn skip_value(&mut self) -> Result<(), JsonError> {
+        match self.next()? {
+            Peeked::StartArray | Peeked::StartObject => self.parser.skip().map_err(JsonError::Parse),
+            _ => Ok(()),
+        }
+    }
+
+    // The visitor may stop before the end of an array or object, so check
+    // that nothing is left over.
+    fn end(&mut self, error: JsonError) -> Result<(), JsonError> {
+        match self.next()? {
+            Peeked::EndArray | Peeked::EndObject => Ok(()),
+            _ => Err(error),
+        }
+    }
+
+    // The parser reports anything after the root value.
+    fn end_document(&mut self) -> Result<(), JsonError> {
+        match self.parser.next_event().map_err(JsonError::Parse)? {
+            None => Ok(()),
+            Some(_) => Err(JsonError::TrailingCharacters),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut EventDeserializer<'_> {
+    type Error = JsonError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Peeked::Null => visitor.visit_unit(),
+            Peeked::Bool(b) => visitor.visit_bool(b),
+            Peeked::Number(n) => visit_number(&n, visitor),
+            Peeked::String | Peeked::Key => visitor.visit_str(self.parser.current()),
+            Peeked::StartArray => {
+                let value = visitor.visit_seq(Events { de: self })?;
+                self.end(JsonError::ExpectedArrayEnd)?;
+                Ok(value)
+            }
+            Peeked::StartObject => {
+                let value = visitor.visit_map(Members::new(self))?;
+                self.end(JsonError::ExpectedMapEnd)?;
+                Ok(value)
+            }
+            Peeked::EndArray | Peeked::EndObject => Err(JsonError::Syntax),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let Peeked::Null = self.peek()? {
+            self.next()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(feature = "arbitrary_precision")]
+        if name == RAW_NUMBER_TOKEN
+            && let Peeked::Number(Number::Raw(_)) = self.peek()?
+            && let Peeked::Number(Number::Raw(s)) = self.next()?
+        {
+            return visitor.visit_string(s);
+        }
+        // `RawValue` gets the exact text of the next value, recorded by the
+        // parser since its first event was read.
+        if name == RAW_VALUE_TOKEN {
+            self.peek()?;
+            self.skip_value()?;
+            let text = String::from_utf8(self.parser.end_capture()).map_err(|_| JsonError::Syntax)?;
+            return visitor.visit_string(trim(&text).to_string());
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Peeked::String => visitor.visit_enum(self.parser.current().into_deserializer()),
+            Peeked::StartObject => {
+                let value = visitor.visit_enum(Variant { de: self })?;
+                self.end(JsonError::ExpectedMapEnd)?;
+                Ok(value)
+            }
+            _ => Err(JsonError::ExpectedEnum),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.skip_value()?;
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+struct Events<'a, 'p> {
+    de: &'a mut EventDeserializer<'p>,
+}
+
+impl<'de> SeqAccess<'de> for Events<'_, '_> {
+    type Error = JsonError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Peeked::EndArray = self.de.peek()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Members<'a, 'p> {
+    de: &'a mut EventDeserializer<'p>,
+    // Keys seen so far, only recorded when duplicates are checked.
+    keys: HashSet<String>,
+    // The text of every value of each repeated key, for `LastWins` and
+    // `CollectAll`.
+    repeated: HashMap<String, Vec<String>>,
+    // The values to hand over for the current key under `CollectAll`.
+    collected: Option<Vec<String>>,
+}
+
+impl<'a, 'p> Members<'a, 'p> {
+    fn new(de: &'a mut EventDeserializer<'p>) -> Self {
+        let repeated = match de.parser.options().duplicate_keys {
+            // The opening brace has just been read. The whole input is in
+            // memory for these policies, so the object can be scanned ahead.
+            DuplicateKeys::LastWins | DuplicateKeys::CollectAll if de.check_duplicates => de
+                .parser
+                .buffered_from(de.parser.offset() - 1)
+                .map(repeated_members)
+                .unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+        Members {
+            de,
+            keys: HashSet::new(),
+            repeated,
+            collected: None,
+        }
+    }
+}
+
+// Scan the object at the start of `json` and return the text of every value
+// of each key that occurs more than once. Nested objects are scanned again
+// when they are reached. Errors are left for the Deserializer to report.
+fn repeated_members(json: &[u8]) -> HashMap<String, Vec<String>> {
+    let mut parser = Parser::new(json).with_options(unlimited());
+    let mut members: HashMap<String, Vec<String>> = HashMap::new();
+    if let Ok(Some(Event::StartObject)) = parser.next_event() {
+        while let Ok(Some(Event::Key(key))) = parser.next_event() {
+            let key = key.to_string();
+            let Ok(Some(span)) = next_span(&mut parser) else {
+                break;
+            };
+            let text = trim(&String::from_utf8_lossy(&json[span])).to_string();
+            members.entry(key).or_default().push(text);
+        }
+    }
+    members.retain(|_, values| values.len() > 1);
+    members
+}
+
+impl<'de> MapAccess<'de> for Members<'_, '_> {
+    type Error = JsonError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        loop {
+            if let Peeked::EndObject = self.de.peek()? {
+                return Ok(None);
+            }
+            if self.de.check_duplicates {
+                let key = self.de.parser.owned_string();
+                let first = self.keys.insert(key.clone());
+                let policy = self.de.parser.options().duplicate_keys;
+                let skip = match policy {
+                    DuplicateKeys::Error if !first => return Err(JsonError::DuplicateKey(key)),
+                    DuplicateKeys::Error => false,
+                    DuplicateKeys::FirstWins | DuplicateKeys::CollectAll => !first,
+                    // Every occurrence but the last is skipped.
+                    DuplicateKeys::LastWins => self.repeated.get_mut(&key).is_some_and(|values| {
+                        values.pop();
+                        !values.is_empty()
+                    }),
+                };
+                if skip {
+                    self.de.next()?;
+                    self.de.skip_value()?;
+                    continue;
+                }
+                if policy == DuplicateKeys::CollectAll {
+                    self.collected = self.repeated.remove(&key);
+                }
+            }
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        // Under `CollectAll` the first occurrence of a repeated key stands for
+        // all of them and its value is an array of every value.
+        let Some(values) = self.collected.take() else {
+            return seed.deserialize(&mut *self.de);
+        };
+        self.de.skip_value()?;
+        let options = self.de.parser.options();
+        let options = ParseOptions {
+            max_depth: options.max_depth.saturating_sub(self.de.parser.depth()),
+            ..options.clone()
+        };
+        seed.deserialize(SeqAccessDeserializer::new(Collected {
+            values: values.into_iter(),
+            options,
+        }))
+    }
+}
+
+// The values of a repeated key under `CollectAll`, each deserialized from its
+// own text.
+struct Collected {
+    values: std::vec::IntoIter<String>,
+    options: ParseOptions,
+}
+
+impl<'de> SeqAccess<'de> for Collected {
+    type Error = JsonError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some(text) = self.values.next() else {
+            return Ok(None);
+        };
+        let parser = Parser::new(text.as_bytes()).with_options(self.options.clone());
+        let mut deserializer = EventDeserializer::new(parser, true);
+        let value = seed.deserialize(&mut deserializer)?;
+        deserializer.end_document()?;
+        Ok(Some(value))
+    }
+}
+
+// Enums use the externally tagged representation, `{ "Variant": DATA }`,
+// once `deserialize_enum` has read the opening brace.
+struct Variant<'a, 'p> {
+    de: &'a mut EventDeserializer<'p>,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'_, '_> {
+    type Error = JsonError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        if let Peeked::EndObject = self.de.peek()? {
+            return Err(JsonError::ExpectedEnum);
+        }
+        let val = seed.deserialize(&mut *self.de)?;
+        Ok((val, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'_, '_> {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Err(JsonError::ExpectedString)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
 struct ValueVisitor;
 
 impl<'de> Visitor<'de> for ValueVisitor {
@@ -633,6 +1266,13 @@ impl<'de> Visitor<'de> for ValueVisitor {
         formatter.write_str("a valid JSON value")
     }
 
+    fn visit_unit<E>(self) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
     fn visit_bool<E>(self, v: bool) -> Result<Value, E>
     where
         E: de::Error,
@@ -654,6 +1294,20 @@ impl<'de> Visitor<'de> for ValueVisitor {
         Ok(Value::Number(v.into()))
     }
 
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Number(v.into()))
+    }
+
     fn visit_f64<E>(self, v: f64) -> Result<Value, E>
     where
         E: de::Error,
@@ -683,11 +1337,143 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         M: MapAccess<'de>,
     {
-        let mut obj = std::collections::BTreeMap::new();
+        let mut obj = Dict::new();
         while let Some((key, value)) = map.next_entry()? {
             obj.insert(key, value);
         }
-        Ok(Value::Object(Dict::from_btree_map(obj)))
+        Ok(Value::Object(obj))
+    }
+}
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON number")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.into())
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.into())
+    }
+
+    // The exact text of an arbitrary precision number.
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_str<E>(self, v: &str) -> Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Number::from_literal(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Number, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+// With `arbitrary_precision` a `Number` keeps every digit, so a decimal type can
+// deserialize through it without going through f64.
+impl<'de> de::Deserialize<'de> for Number {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[cfg(feature = "arbitrary_precision")]
+        return deserializer.deserialize_newtype_struct(RAW_NUMBER_TOKEN, NumberVisitor);
+        #[cfg(not(feature = "arbitrary_precision"))]
+        deserializer.deserialize_any(NumberVisitor)
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = Box<RawValue>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Box<RawValue>, E>
+    where
+        E: de::Error,
+    {
+        RawValue::parse_with_options(v, unlimited())
+            .map(ToOwned::to_owned)
+            .map_err(E::custom)
+    }
+}
+
+struct BorrowedRawValueVisitor;
+
+impl<'de> Visitor<'de> for BorrowedRawValueVisitor {
+    type Value = &'de RawValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value borrowed from the input")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<&'de RawValue, E>
+    where
+        E: de::Error,
+    {
+        RawValue::parse_with_options(v, unlimited()).map_err(E::custom)
+    }
+}
+
+// `Box<RawValue>` copies the text of the value; `&RawValue` borrows it and
+// only works with `from_str`. The depth limit has already been applied by the
+// Deserializer, so the text is checked again without one.
+impl<'de> de::Deserialize<'de> for Box<RawValue> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, RawValueVisitor)
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserialize<'de> for &'a RawValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, BorrowedRawValueVisitor)
     }
 }
 
@@ -699,3 +1485,129 @@ impl<'de> de::Deserialize<'de> for Value {
         deserializer.deserialize_any(ValueVisitor)
     }
 }
+
+// A `Value` that has already been parsed can itself act as a Deserializer.
+// Hand a number to the visitor. Integers stay exact, everything else becomes
+// the nearest f64. A raw literal with a fraction or exponent is a float even
+// if its value is integral.
+fn visit_number<'de, V>(n: &Number, visitor: V) -> Result<V::Value, JsonError>
+where
+    V: Visitor<'de>,
+{
+    match n {
+        Number::Int(n) => visitor.visit_i64(*n),
+        Number::UInt(n) => visitor.visit_u64(*n),
+        Number::Int128(n) => visitor.visit_i128(*n),
+        Number::UInt128(n) => visitor.visit_u128(*n),
+        Number::Float(n) => visitor.visit_f64(*n),
+        #[cfg(feature = "arbitrary_precision")]
+        Number::Raw(_) if !n.is_integer() => match n.as_f64() {
+            Some(f) => visitor.visit_f64(f),
+            None => Err(de::Error::custom(format_args!("number {n} is out of range"))),
+        },
+        #[cfg(feature = "arbitrary_precision")]
+        Number::Raw(_) => {
+            if let Some(i) = n.as_i64() {
+                visitor.visit_i64(i)
+            } else if let Some(u) = n.as_u64() {
+                visitor.visit_u64(u)
+            } else if let Some(i) = n.as_i128() {
+                visitor.visit_i128(i)
+            } else if let Some(u) = n.as_u128() {
+                visitor.visit_u128(u)
+            } else if let Some(f) = n.as_f64() {
+                visitor.visit_f64(f)
+            } else {
+                Err(de::Error::custom(format_args!("number {n} is out of range")))
+            }
+        }
+    }
+}
+
+// This is what `from_value` is built on.
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = JsonError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => visit_number(&n, visitor),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(arr) => {
+                let mut seq = SeqDeserializer::new(arr.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(obj) => {
+                let mut map = MapDeserializer::new(obj.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(feature = "arbitrary_precision")]
+        if let (RAW_NUMBER_TOKEN, Value::Number(Number::Raw(s))) = (name, &self) {
+            return visitor.visit_string(s.clone());
+        }
+        // A parsed tree no longer has its original text, so a `RawValue` gets
+        // the value written out again.
+        if name == RAW_VALUE_TOKEN {
+            return visitor.visit_string(self.to_string());
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Enums use the same externally tagged representation as the text
+    // Deserializer: either `"Variant"` or `{ "Variant": DATA }`.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::Object(obj) if obj.len() == 1 => MapAccessDeserializer::new(MapDeserializer::new(obj.into_iter()))
+                .deserialize_enum(name, variants, visitor),
+            _ => Err(JsonError::ExpectedEnum),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, JsonError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
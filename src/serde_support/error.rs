@@ -1,3 +1,4 @@
+use crate::error::{ParseError, WriteError};
 use std::fmt::{self, Display};
 
 #[derive(Debug)]
@@ -9,6 +10,13 @@ pub enum JsonError {
     // field is missing.
     Message(String),
 
+    // Errors reported by the `Parser`, for example while `from_reader` reads
+    // the input.
+    Parse(ParseError),
+
+    // A float the Serializer cannot write under the chosen `WriteOptions`.
+    Write(WriteError),
+
     // Zero or more variants that can be created directly by the Serializer and
     // Deserializer without going through `ser::Error` and `de::Error`. These
     // are specific to the format, in this case JSON.
@@ -16,6 +24,8 @@ pub enum JsonError {
     Syntax,
     ExpectedBoolean,
     ExpectedInteger,
+    ExpectedNumber,
+    NumberOutOfRange,
     ExpectedString,
     ExpectedNull,
     ExpectedArray,
@@ -27,16 +37,27 @@ pub enum JsonError {
     ExpectedMapEnd,
     ExpectedEnum,
     TrailingCharacters,
+    DepthLimitExceeded,
+    DocumentTooLarge,
+    StringTooLong,
+    ArrayTooLong,
+    ObjectTooLong,
+    TooManyNodes,
+    DuplicateKey(String),
 }
 
 impl Display for JsonError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
             JsonError::Message(msg) => formatter.write_str(msg),
+            JsonError::Parse(err) => err.fmt(formatter),
+            JsonError::Write(err) => err.fmt(formatter),
             JsonError::Eof => formatter.write_str("unexpected end of input"),
             JsonError::Syntax => formatter.write_str("syntax error"),
             JsonError::ExpectedBoolean => formatter.write_str("expected boolean"),
             JsonError::ExpectedInteger => formatter.write_str("expectedInteger"),
+            JsonError::ExpectedNumber => formatter.write_str("expected number"),
+            JsonError::NumberOutOfRange => formatter.write_str("number out of range"),
             JsonError::ExpectedString => formatter.write_str("expected string"),
             JsonError::ExpectedNull => formatter.write_str("expected null"),
             JsonError::ExpectedArray => formatter.write_str("expected array"),
@@ -48,6 +69,13 @@ impl Display for JsonError {
             JsonError::ExpectedMapEnd => formatter.write_str("expected map end"),
             JsonError::ExpectedEnum => formatter.write_str("expected enum"),
             JsonError::TrailingCharacters => formatter.write_str("trailing characters"),
+            JsonError::DepthLimitExceeded => formatter.write_str("nesting depth limit exceeded"),
+            JsonError::DocumentTooLarge => formatter.write_str("document size limit exceeded"),
+            JsonError::StringTooLong => formatter.write_str("string length limit exceeded"),
+            JsonError::ArrayTooLong => formatter.write_str("array length limit exceeded"),
+            JsonError::ObjectTooLong => formatter.write_str("object member limit exceeded"),
+            JsonError::TooManyNodes => formatter.write_str("value count limit exceeded"),
+            JsonError::DuplicateKey(key) => write!(formatter, "duplicate key `{key}`"),
         }
     }
 }
@@ -1,10 +1,33 @@
+use crate::number::{Number, write_float};
+use crate::options::WriteOptions;
+use crate::raw::RawValue;
+#[cfg(feature = "arbitrary_precision")]
+use crate::serde_support::RAW_NUMBER_TOKEN;
+use crate::serde_support::RAW_VALUE_TOKEN;
 use crate::serde_support::error::JsonError;
-use crate::{number::Number, value::Value};
+use crate::value::Value;
 use serde::ser::{
     Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
     SerializeTupleStruct, SerializeTupleVariant, Serializer,
 };
 
+impl Serialize for Number {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Number::Int(n) => serializer.serialize_i64(n),
+            Number::UInt(n) => serializer.serialize_u64(n),
+            Number::Int128(n) => serializer.serialize_i128(n),
+            Number::UInt128(n) => serializer.serialize_u128(n),
+            Number::Float(n) => serializer.serialize_f64(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Raw(ref s) => serializer.serialize_newtype_struct(RAW_NUMBER_TOKEN, s),
+        }
+    }
+}
+
 impl Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -13,9 +36,7 @@ impl Serialize for Value {
         match self {
             Value::Null => serializer.serialize_unit(),
             Value::Bool(b) => serializer.serialize_bool(*b),
-            Value::Number(Number::Int(n)) => serializer.serialize_i64(*n),
-            Value::Number(Number::UInt(n)) => serializer.serialize_u64(*n),
-            Value::Number(Number::Float(n)) => serializer.serialize_f64(*n),
+            Value::Number(n) => n.serialize(serializer),
             Value::String(s) => serializer.serialize_str(s),
             Value::Array(arr) => {
                 let mut seq = serializer.serialize_seq(Some(arr.len()))?;
@@ -25,9 +46,19 @@ impl Serialize for Value {
                 seq.end()
             }
             Value::Object(obj) => {
+                // Backends without a fixed iteration order are written sorted by key.
                 let mut map = serializer.serialize_map(Some(obj.len()))?;
-                for (key, value) in obj.iter() {
-                    map.serialize_entry(key, value)?;
+                match obj.sorted_entries() {
+                    Some(entries) => {
+                        for (key, value) in entries {
+                            map.serialize_entry(key, value)?;
+                        }
+                    }
+                    None => {
+                        for (key, value) in obj.iter() {
+                            map.serialize_entry(key, value)?;
+                        }
+                    }
                 }
                 map.end()
             }
@@ -35,26 +66,52 @@ impl Serialize for Value {
     }
 }
 
+// The text of a `RawValue` is already valid JSON and is written back untouched.
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_VALUE_TOKEN, self.get())
+    }
+}
+
 pub fn to_string<T>(value: &T) -> Result<String, JsonError>
 where
     T: Serialize,
 {
-    let mut serializer = JsonSerializer::new();
+    to_string_with_options(value, WriteOptions::default())
+}
+
+pub fn to_string_with_options<T>(value: &T, options: WriteOptions) -> Result<String, JsonError>
+where
+    T: Serialize,
+{
+    let mut serializer = JsonSerializer::new(options);
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
 
 impl JsonSerializer {
-    fn new() -> Self {
-        JsonSerializer { output: String::new() }
+    fn new(options: WriteOptions) -> Self {
+        JsonSerializer {
+            output: String::new(),
+            options,
+            raw_text: false,
+        }
     }
 }
 
 struct JsonSerializer {
     output: String,
+    // How floats are written.
+    options: WriteOptions,
+    // Set while the text of a `RawValue` or of an arbitrary precision number
+    // is serialized. `serialize_str` then writes it as is instead of quoting it.
+    raw_text: bool,
 }
 
-impl<'a> Serializer for &'a mut JsonSerializer {
+impl Serializer for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -90,8 +147,8 @@ impl<'a> Serializer for &'a mut JsonSerializer {
     }
 
     fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
-        let _ = v;
-        Err(serde::ser::Error::custom("i128 is not supported"))
+        self.output += &v.to_string();
+        Ok(())
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
@@ -112,16 +169,19 @@ impl<'a> Serializer for &'a mut JsonSerializer {
     }
 
     fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
-        let _ = v;
-        Err(serde::ser::Error::custom("u128 is not supported"))
+        self.output += &v.to_string();
+        Ok(())
     }
 
+    // Floats are written with the fewest digits that parse back to the same
+    // value, so an f32 is not widened to f64 first.
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.serialize_f64(f64::from(v))
+        self.output += &write_float(v, &self.options).map_err(JsonError::Write)?;
+        Ok(())
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.output += &v.to_string();
+        self.output += &write_float(v, &self.options).map_err(JsonError::Write)?;
         Ok(())
     }
 
@@ -130,9 +190,11 @@ impl<'a> Serializer for &'a mut JsonSerializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.output += "\"";
-        self.output += v;
-        self.output += "\"";
+        if std::mem::take(&mut self.raw_text) {
+            self.output += v;
+        } else {
+            self.output += &Value::stringify_string(v);
+        }
         Ok(())
     }
 
@@ -175,10 +237,22 @@ impl<'a> Serializer for &'a mut JsonSerializer {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
+        // A `RawValue` or an arbitrary precision number hands over its text,
+        // which is already valid JSON and is written without quotes.
+        #[cfg(feature = "arbitrary_precision")]
+        let raw = name == RAW_VALUE_TOKEN || name == RAW_NUMBER_TOKEN;
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let raw = name == RAW_VALUE_TOKEN;
+        if raw {
+            self.raw_text = true;
+            let result = value.serialize(&mut *self);
+            self.raw_text = false;
+            return result;
+        }
         value.serialize(self)
     }
 
@@ -260,7 +334,7 @@ impl<'a> Serializer for &'a mut JsonSerializer {
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a> SerializeSeq for &'a mut JsonSerializer {
+impl SerializeSeq for &mut JsonSerializer {
     // Must match the `Ok` type of the serializer.
     type Ok = ();
     // Must match the `Error` type of the serializer.
@@ -285,7 +359,7 @@ impl<'a> SerializeSeq for &'a mut JsonSerializer {
 }
 
 // Same thing but for tuples.
-impl<'a> SerializeTuple for &'a mut JsonSerializer {
+impl SerializeTuple for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -306,7 +380,7 @@ impl<'a> SerializeTuple for &'a mut JsonSerializer {
 }
 
 // Same thing but for tuple structs.
-impl<'a> SerializeTupleStruct for &'a mut JsonSerializer {
+impl SerializeTupleStruct for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -335,7 +409,7 @@ impl<'a> SerializeTupleStruct for &'a mut JsonSerializer {
 //
 // So the `end` method in this impl is responsible for closing both the `]` and
 // the `}`.
-impl<'a> SerializeTupleVariant for &'a mut JsonSerializer {
+impl SerializeTupleVariant for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -363,7 +437,7 @@ impl<'a> SerializeTupleVariant for &'a mut JsonSerializer {
 // `serialize_entry` method allows serializers to optimize for the case where
 // key and value are both available simultaneously. In JSON it doesn't make a
 // difference so the default behavior for `serialize_entry` is fine.
-impl<'a> SerializeMap for &'a mut JsonSerializer {
+impl SerializeMap for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -404,7 +478,7 @@ impl<'a> SerializeMap for &'a mut JsonSerializer {
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> SerializeStruct for &'a mut JsonSerializer {
+impl SerializeStruct for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
@@ -428,7 +502,7 @@ impl<'a> SerializeStruct for &'a mut JsonSerializer {
 
 // Similar to `SerializeTupleVariant`, here the `end` method is responsible for
 // closing both of the curly braces opened by `serialize_struct_variant`.
-impl<'a> SerializeStructVariant for &'a mut JsonSerializer {
+impl SerializeStructVariant for &mut JsonSerializer {
     type Ok = ();
     type Error = JsonError;
 
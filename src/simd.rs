@@ -0,0 +1,655 @@
+// 两阶段解析：第一阶段按64字节一块找出所有结构字符的位置（结构索引），x86_64上使用SSE2或AVX2；
+// 第二阶段沿着索引构建Value，不必逐字节跳过空白和字符串内容。
+// 遇到任何错误或超出限制时都改用Parser从头解析，所以结果和错误信息与Value::parse完全相同
+use crate::context::Context;
+use crate::error::ParseError;
+use crate::options::{DuplicateKeys, ParseOptions};
+use crate::parser::Parser;
+use crate::value::{Value, ValueBuilder};
+
+impl Value {
+    pub fn parse_simd(json: &str) -> Result<Value, ParseError> {
+        Value::parse_slice_simd(json.as_bytes())
+    }
+
+    pub fn parse_slice_simd(json: &[u8]) -> Result<Value, ParseError> {
+        Value::parse_slice_simd_with_options(json, ParseOptions::default())
+    }
+
+    pub fn parse_simd_with_options(json: &str, options: ParseOptions) -> Result<Value, ParseError> {
+        Value::parse_slice_simd_with_options(json.as_bytes(), options)
+    }
+
+    fn parse_slice_simd_with_options(json: &[u8], options: ParseOptions) -> Result<Value, ParseError> {
+        match parse_indexed(json, &options, Kernel::detect()) {
+            Some(v) => Ok(v),
+            None => Value::parse_document(Parser::new(json).with_options(options)),
+        }
+    }
+}
+
+// 一个64字节块中各类字符的位图，第i位对应块中第i个字节
+struct Masks {
+    quote: u64,
+    backslash: u64,
+    // { } [ ] : ,
+    operator: u64,
+    whitespace: u64,
+}
+
+// 第一阶段使用的指令集，运行时按CPU支持的情况选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kernel {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+}
+
+impl Kernel {
+    pub fn detect() -> Kernel {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Kernel::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return Kernel::Sse2;
+            }
+        }
+        Kernel::Scalar
+    }
+
+    // 当前CPU上可用的所有实现，用于差分测试
+    #[cfg(test)]
+    pub fn available() -> Vec<Kernel> {
+        let mut kernels = vec![Kernel::Scalar];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("sse2") {
+                kernels.push(Kernel::Sse2);
+            }
+            if is_x86_feature_detected!("avx2") {
+                kernels.push(Kernel::Avx2);
+            }
+        }
+        kernels
+    }
+}
+
+// 构建结构索引：对象和数组的括号、冒号、逗号、字符串的左引号，以及其他值（数字和字面量）的第一个字节
+pub(crate) fn structural_index(json: &[u8], kernel: Kernel) -> Vec<u32> {
+    let mut index = Vec::with_capacity(json.len() / 8);
+    match kernel {
+        Kernel::Scalar => build_index(json, &mut index, classify_scalar),
+        // SAFETY: 只有在detect或available确认CPU支持对应指令集后才会选择这些实现
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse2 => unsafe { x86::index_sse2(json, &mut index) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { x86::index_avx2(json, &mut index) },
+    }
+    index
+}
+
+#[inline(always)]
+fn build_index(json: &[u8], index: &mut Vec<u32>, classify: impl Fn(&[u8; 64]) -> Masks) {
+    let mut scanner = Scanner::default();
+    let mut chunks = json.chunks_exact(64);
+    let mut base = 0u32;
+    for block in &mut chunks {
+        let bits = scanner.next(classify(block.try_into().unwrap()));
+        flatten(bits, base, index);
+        base = base.wrapping_add(64);
+    }
+    // 最后不足64字节的部分用空白补齐
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let mut block = [b' '; 64];
+        block[..rest.len()].copy_from_slice(rest);
+        let bits = scanner.next(classify(&block));
+        flatten(bits, base, index);
+    }
+}
+
+#[inline(always)]
+fn flatten(mut bits: u64, base: u32, index: &mut Vec<u32>) {
+    while bits != 0 {
+        index.push(base + bits.trailing_zeros());
+        bits &= bits - 1;
+    }
+}
+
+// 跨块保存的状态
+#[derive(Default)]
+struct Scanner {
+    // 上一块末尾的反斜杠是否转义了本块的第一个字节
+    prev_escaped: u64,
+    // 上一块结束时是否在字符串中，全1或全0
+    in_string: u64,
+    // 上一块的最后一个字节是否属于数字或字面量
+    prev_scalar: u64,
+}
+
+impl Scanner {
+    #[inline(always)]
+    fn next(&mut self, masks: Masks) -> u64 {
+        let escaped = self.escaped(masks.backslash);
+        let quote = masks.quote & !escaped;
+        // 左引号到右引号之前（含左引号，不含右引号）的字节都在字符串中
+        let in_string = prefix_xor(quote) ^ self.in_string;
+        self.in_string = ((in_string as i64) >> 63) as u64;
+        // 字符串之外既不是空白也不是结构字符的字节属于数字或字面量，只记录每一段的第一个字节
+        let scalar = !(masks.operator | masks.whitespace | quote | in_string);
+        let follows_scalar = (scalar << 1) | self.prev_scalar;
+        self.prev_scalar = scalar >> 63;
+        (masks.operator & !in_string) | (quote & in_string) | (scalar & !follows_scalar)
+    }
+
+    // 被反斜杠转义的字节：连续的反斜杠两两配对，奇数个时最后一个转义它后面的字节
+    #[inline(always)]
+    fn escaped(&mut self, backslash: u64) -> u64 {
+        const EVEN_BITS: u64 = 0x5555_5555_5555_5555;
+        let backslash = backslash & !self.prev_escaped;
+        let follows_escape = (backslash << 1) | self.prev_escaped;
+        // 从奇数位开始的反斜杠序列，加法的进位会越过整段序列
+        let odd_starts = backslash & !EVEN_BITS & !follows_escape;
+        let (even_starts, overflow) = odd_starts.overflowing_add(backslash);
+        self.prev_escaped = overflow as u64;
+        let invert = even_starts << 1;
+        (EVEN_BITS ^ invert) & follows_escape
+    }
+}
+
+// 第i位是第0到第i位的异或
+#[inline(always)]
+fn prefix_xor(mut bits: u64) -> u64 {
+    bits ^= bits << 1;
+    bits ^= bits << 2;
+    bits ^= bits << 4;
+    bits ^= bits << 8;
+    bits ^= bits << 16;
+    bits ^= bits << 32;
+    bits
+}
+
+#[inline(always)]
+fn classify_scalar(block: &[u8; 64]) -> Masks {
+    let mut masks = Masks {
+        quote: 0,
+        backslash: 0,
+        operator: 0,
+        whitespace: 0,
+    };
+    for (i, &b) in block.iter().enumerate() {
+        let bit = 1u64 << i;
+        match b {
+            b'"' => masks.quote |= bit,
+            b'\\' => masks.backslash |= bit,
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => masks.operator |= bit,
+            b' ' | b'\t' | b'\n' | b'\r' => masks.whitespace |= bit,
+            _ => {}
+        }
+    }
+    masks
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{Masks, build_index};
+    use std::arch::x86_64::*;
+
+    // '['和']'的0x20位置1后分别等于'{'和'}'，所以四种括号只需比较两次
+    macro_rules! classify {
+        ($load:ident, $cmpeq:ident, $set1:ident, $or:ident, $movemask:ident, $width:literal, $block:ident) => {{
+            let mut masks = Masks {
+                quote: 0,
+                backslash: 0,
+                operator: 0,
+                whitespace: 0,
+            };
+            for lane in 0..64 / $width {
+                // SAFETY: 每次读取的$width个字节都在64字节的块内
+                let v = unsafe { $load($block.as_ptr().add(lane * $width).cast()) };
+                let eq = |c: u8| $cmpeq(v, $set1(c as i8));
+                let bits = |m| ($movemask(m) as u32 as u64) << (lane * $width);
+                let folded = $or(v, $set1(0x20));
+                masks.quote |= bits(eq(b'"'));
+                masks.backslash |= bits(eq(b'\\'));
+                masks.operator |= bits($or(
+                    $or($cmpeq(folded, $set1(b'{' as i8)), $cmpeq(folded, $set1(b'}' as i8))),
+                    $or(eq(b':'), eq(b',')),
+                ));
+                masks.whitespace |= bits($or($or(eq(b' '), eq(b'\t')), $or(eq(b'\n'), eq(b'\r'))));
+            }
+            masks
+        }};
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn classify_sse2(block: &[u8; 64]) -> Masks {
+        classify!(
+            _mm_loadu_si128,
+            _mm_cmpeq_epi8,
+            _mm_set1_epi8,
+            _mm_or_si128,
+            _mm_movemask_epi8,
+            16,
+            block
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn classify_avx2(block: &[u8; 64]) -> Masks {
+        classify!(
+            _mm256_loadu_si256,
+            _mm256_cmpeq_epi8,
+            _mm256_set1_epi8,
+            _mm256_or_si256,
+            _mm256_movemask_epi8,
+            32,
+            block
+        )
+    }
+
+    // 整个扫描循环都用对应的指令集编译，使分类函数可以内联
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn index_sse2(json: &[u8], index: &mut Vec<u32>) {
+        build_index(json, index, |block| classify_sse2(block));
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn index_avx2(json: &[u8], index: &mut Vec<u32>) {
+        build_index(json, index, |block| classify_avx2(block));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    ArrayStart,
+    ObjectStart,
+    Key,
+    AfterValue,
+}
+
+// 第二阶段：沿着结构索引构建Value。相邻两个索引之间只有空白，或者是前一个值的剩余部分；
+// 返回None表示需要由Parser重新解析以得到准确的错误
+fn parse_indexed(json: &[u8], options: &ParseOptions, kernel: Kernel) -> Option<Value> {
+    if json.len() > options.max_document_size || u32::try_from(json.len()).is_err() {
+        return None;
+    }
+    let index = structural_index(json, kernel);
+    let mut positions = index.iter().map(|&p| p as usize).peekable();
+    let mut builder: ValueBuilder = ValueBuilder::new().with_duplicate_keys(options.duplicate_keys);
+    // 每层容器是否为对象，以及已有的元素或成员个数
+    let mut containers: Vec<(bool, usize)> = Vec::new();
+    let mut scratch = String::new();
+    let mut nodes = 0;
+    let mut state = State::Value;
+    loop {
+        let pos = positions.next()?;
+        let b = json[pos];
+        let done = match state {
+            State::AfterValue => match (containers.last()?.0, b) {
+                (false, b',') => {
+                    state = State::Value;
+                    continue;
+                }
+                (true, b',') => {
+                    state = State::Key;
+                    continue;
+                }
+                (false, b']') | (true, b'}') => {
+                    containers.pop();
+                    builder.end()
+                }
+                _ => return None,
+            },
+            State::ObjectStart if b == b'}' => {
+                containers.pop();
+                builder.end()
+            }
+            State::ObjectStart | State::Key => {
+                if b != b'"' || containers.last()?.1 >= options.max_object_length {
+                    return None;
+                }
+                let mut context = Context::new(&json[pos..]);
+                let borrowed = Parser::parse_string(&mut context, options.max_string_length, &mut scratch).ok()?;
+                let key = borrowed.unwrap_or(&scratch);
+                let colon = positions.next()?;
+                if json[colon] != b':' || colon < pos + context.offset() {
+                    return None;
+                }
+                if options.duplicate_keys == DuplicateKeys::Error && builder.contains_key(key) {
+                    return None;
+                }
+                builder.key(key.to_string());
+                state = State::Value;
+                continue;
+            }
+            State::ArrayStart if b == b']' => {
+                containers.pop();
+                builder.end()
+            }
+            State::Value | State::ArrayStart => {
+                let too_deep = matches!(b, b'[' | b'{') && containers.len() >= options.max_depth;
+                let too_long = matches!(containers.last(), Some(&(false, n)) if n >= options.max_array_length);
+                if too_deep || too_long || nodes >= options.max_nodes {
+                    return None;
+                }
+                nodes += 1;
+                let mut context = Context::new(&json[pos..]);
+                let value = match b {
+                    b'[' => {
+                        containers.push((false, 0));
+                        builder.start_array();
+                        state = State::ArrayStart;
+                        continue;
+                    }
+                    b'{' => {
+                        containers.push((true, 0));
+                        builder.start_object();
+                        state = State::ObjectStart;
+                        continue;
+                    }
+                    b'"' => {
+                        let borrowed =
+                            Parser::parse_string(&mut context, options.max_string_length, &mut scratch).ok()?;
+                        Value::String(borrowed.unwrap_or(&scratch).to_string())
+                    }
+                    b'-' | b'0'..=b'9' => Value::Number(Parser::parse_number(&mut context).ok()?),
+                    b'n' | b't' | b'f' => match Parser::parse_literal(&mut context).ok()? {
+                        Some(b) => Value::Bool(b),
+                        None => Value::Null,
+                    },
+                    _ => return None,
+                };
+                // 值之后要么紧接着下一个索引，要么是空白
+                let end = pos + context.offset();
+                let next = positions.peek().copied();
+                if next.is_some_and(|next| next < end)
+                    || (end < json.len() && next != Some(end) && !matches!(json[end], b' ' | b'\t' | b'\n' | b'\r'))
+                {
+                    return None;
+                }
+                builder.value(value)
+            }
+        };
+        if let Some(root) = done {
+            return match positions.next() {
+                None => Some(root),
+                Some(_) => None,
+            };
+        }
+        if let Some((_, n)) = containers.last_mut() {
+            *n += 1;
+        }
+        state = State::AfterValue;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::DuplicateKeys;
+
+    // 逐字节计算的结构索引，作为各实现的参照
+    fn reference_index(json: &[u8]) -> Vec<u32> {
+        let mut index = Vec::new();
+        let (mut escaped, mut in_string, mut prev_scalar) = (false, false, false);
+        for (i, &b) in json.iter().enumerate() {
+            let is_escaped = escaped;
+            escaped = b == b'\\' && !is_escaped;
+            if b == b'"' && !is_escaped {
+                in_string = !in_string;
+                if in_string {
+                    index.push(i as u32);
+                }
+                prev_scalar = false;
+            } else if in_string {
+                prev_scalar = false;
+            } else if matches!(b, b'{' | b'}' | b'[' | b']' | b':' | b',') {
+                index.push(i as u32);
+                prev_scalar = false;
+            } else if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+                prev_scalar = false;
+            } else {
+                if !prev_scalar {
+                    index.push(i as u32);
+                }
+                prev_scalar = true;
+            }
+        }
+        index
+    }
+
+    // 用简单的线性同余生成器构造随机文档，偏向于产生跨越64字节边界的转义和字符串
+    fn random_document(seed: &mut u64, len: usize) -> String {
+        let mut next = |bound: u64| {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (*seed >> 33) % bound
+        };
+        let pieces = [
+            "{", "}", "[", "]", ":", ",", " ", "\n", "\"", "\\", "\\\\", "\\\"", "a", "1", "-2.5e3", "true", "null",
+            "中文", "\t", "\"k\":",
+        ];
+        let mut s = String::new();
+        while s.len() < len {
+            s.push_str(pieces[next(pieces.len() as u64) as usize]);
+        }
+        s
+    }
+
+    #[test]
+    fn structural_index_kernels() {
+        let fixed = [
+            r#"{"a": [1, 2.5, "x\"y", {"b\\": null}], "c": true}"#.to_string(),
+            format!(r#"["{}\"", 1]"#, "\\".repeat(63)),
+            format!(r#"["{}", 1]"#, "\\".repeat(64)),
+            format!("{}\"{}\"", " ".repeat(63), "x".repeat(70)),
+            format!("[{}]", "123,".repeat(40)),
+        ];
+        let mut seed = 7;
+        let random = (0..500).map(|i| random_document(&mut seed, i % 300));
+        for json in fixed.into_iter().chain(random) {
+            let expected = reference_index(json.as_bytes());
+            for kernel in Kernel::available() {
+                assert_eq!(
+                    structural_index(json.as_bytes(), kernel),
+                    expected,
+                    "{kernel:?} {json:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn simd_matches_parser() {
+        let docs = [
+            r#"{"a": [1, 2.5, "x\"y", {"b": null}], "c": true, "d": "中文\u4e2d"}"#.to_string(),
+            format!(
+                r#"{{"k": [{}], "s": "{}"}}"#,
+                "-1.5e3, 0, 18446744073709551616, ".repeat(8) + "1",
+                "\\n".repeat(40)
+            ),
+            format!("[{}0]", "[1, {\"x\": \"y\"}, false],\n".repeat(20)),
+            "  \"\"  ".to_string(),
+            "0".to_string(),
+        ];
+        for json in &docs {
+            for kernel in Kernel::available() {
+                let expected = Value::parse(json).unwrap();
+                assert_eq!(
+                    parse_indexed(json.as_bytes(), &ParseOptions::default(), kernel),
+                    Some(expected)
+                );
+            }
+            assert_eq!(Value::parse_simd(json).unwrap(), Value::parse(json).unwrap());
+        }
+    }
+
+    // 随机生成合法的文档，值之间插入随机的空白
+    fn random_valid(next: &mut impl FnMut(u64) -> u64, depth: usize, out: &mut String) {
+        let ws = ["", " ", "\n  ", "\t", "\r\n"];
+        out.push_str(ws[next(5) as usize]);
+        let scalars = [
+            "0",
+            "-1",
+            "3.25",
+            "1e-7",
+            "12345678901234567890",
+            "true",
+            "false",
+            "null",
+            "\"\"",
+            "\"plain\"",
+            "\"esc\\\"aped\\\\\"",
+            "\"\\u00e9\\n中\"",
+        ];
+        match next(if depth > 4 { 1 } else { 4 }) {
+            0 => out.push_str(scalars[next(scalars.len() as u64) as usize]),
+            1 => {
+                out.push('[');
+                for i in 0..next(5) {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    random_valid(next, depth + 1, out);
+                }
+                out.push_str(ws[next(5) as usize]);
+                out.push(']');
+            }
+            _ => {
+                out.push('{');
+                for i in 0..next(5) {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&format!(
+                        "{}\"key{}\\\\{}\":",
+                        ws[next(5) as usize],
+                        next(4),
+                        "x".repeat(next(70) as usize)
+                    ));
+                    random_valid(next, depth + 1, out);
+                }
+                out.push_str(ws[next(5) as usize]);
+                out.push('}');
+            }
+        }
+        out.push_str(ws[next(5) as usize]);
+    }
+
+    #[test]
+    fn simd_random_documents() {
+        let mut seed: u64 = 3;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..500 {
+            let mut json = String::new();
+            random_valid(&mut next, 0, &mut json);
+            let expected = Value::parse(&json).unwrap();
+            for kernel in Kernel::available() {
+                assert_eq!(
+                    structural_index(json.as_bytes(), kernel),
+                    reference_index(json.as_bytes())
+                );
+                let actual = parse_indexed(json.as_bytes(), &ParseOptions::default(), kernel);
+                assert_eq!(actual.as_ref(), Some(&expected), "{kernel:?} {json:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn simd_errors_match_parser() {
+        let inputs = [
+            "",
+            " ",
+            "[1, 2",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "\"abc",
+            "[\"\\x\"]",
+            "[1] 2",
+            "1x",
+            "tru",
+            "[1 2]",
+            "{1: 2}",
+            "[01]",
+            "[1e309]",
+            "[\"a\u{1}\"]",
+            "\"a\"b",
+            "[-]",
+            "{\"a\":1}}",
+            "]",
+            "[\"\\\"]",
+            "nul l",
+        ];
+        let mut seed = 11;
+        let random = (0..2000).map(|i| random_document(&mut seed, 1 + i % 120));
+        for json in inputs.iter().map(|s| s.to_string()).chain(random) {
+            let expected = Value::parse(&json);
+            let actual = Value::parse_simd(&json);
+            match (&expected, &actual) {
+                (Ok(a), Ok(b)) => assert_eq!(a, b, "{json:?}"),
+                (Err(a), Err(b)) => assert_eq!(a, b, "{json:?}"),
+                _ => panic!("{json:?}: {expected:?} != {actual:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn simd_options() {
+        let json = r#"{"a": [1, 2, 3], "b": {"c": "long string"}, "a": 4}"#;
+        let options = [
+            ParseOptions {
+                max_depth: 1,
+                ..Default::default()
+            },
+            ParseOptions {
+                max_array_length: 2,
+                ..Default::default()
+            },
+            ParseOptions {
+                max_object_length: 2,
+                ..Default::default()
+            },
+            ParseOptions {
+                max_string_length: 5,
+                ..Default::default()
+            },
+            ParseOptions {
+                max_nodes: 5,
+                ..Default::default()
+            },
+            ParseOptions {
+                max_document_size: 10,
+                ..Default::default()
+            },
+            ParseOptions {
+                duplicate_keys: DuplicateKeys::Error,
+                ..Default::default()
+            },
+            ParseOptions {
+                duplicate_keys: DuplicateKeys::CollectAll,
+                ..Default::default()
+            },
+            ParseOptions {
+                duplicate_keys: DuplicateKeys::FirstWins,
+                ..Default::default()
+            },
+        ];
+        for options in options {
+            let expected = Value::parse_with_options(json, options.clone());
+            assert_eq!(
+                Value::parse_simd_with_options(json, options.clone()),
+                expected,
+                "{options:?}"
+            );
+        }
+    }
+}
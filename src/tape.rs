@@ -0,0 +1,588 @@
+use crate::error::{ParseError, ParseErrorKind, WriteError};
+use crate::number::Number;
+use crate::options::{DuplicateKeys, ParseOptions, WriteOptions};
+use crate::parser::{Event, Parser};
+use crate::value::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::{Deref, Index};
+
+// 磁带上的节点，按文档顺序排列；容器节点之后紧接着它的所有子孙节点，
+// 对象的每个成员是一个Key节点加上值的节点。位置和长度都用u32保存，每个节点只占16字节
+#[derive(Debug)]
+enum Node {
+    Null,
+    Bool(bool),
+    // 数字在numbers中的下标
+    Number(u32),
+    // 字符串内容在strings中的范围
+    String(u32, u32),
+    Key(u32, u32),
+    // 元素个数或按重复键策略合并后的成员个数、包括自身在内的子树节点数，
+    // 以及子节点的位置在Storage::children中的起点
+    Array(u32, u32, u32),
+    Object(u32, u32, u32),
+    // 磁带的最后一个节点
+    End(Box<Storage>),
+}
+
+// 所有字符串的内容、所有数字、所有容器的子节点位置，以及构建Value时使用的重复键策略
+#[derive(Debug)]
+struct Storage {
+    strings: String,
+    numbers: Vec<Number>,
+    // 子节点相对于容器节点的位置。数组是每个元素；对象先是每个键按策略选中的成员的Key节点，
+    // 按键第一次出现的顺序排列，接着是同样这些位置按键排序，用于二分查找
+    children: Vec<u32>,
+    policy: DuplicateKeys,
+}
+
+impl Node {
+    fn size(&self) -> usize {
+        match self {
+            Node::Array(_, size, _) | Node::Object(_, size, _) => *size as usize,
+            _ => 1,
+        }
+    }
+}
+
+// 只读的紧凑文档：所有节点放在一个Vec中，所有字符串放在一个String中，
+// 构建时不为每个值单独分配内存。通过Deref得到根节点的ValueRef
+#[derive(Debug)]
+pub struct Tape {
+    nodes: Vec<Node>,
+}
+
+// 磁带上某个值的视图，即从该值的节点到磁带末尾的切片；
+// 是动态大小类型，总是以&ValueRef的形式使用，这样才能实现Index
+#[repr(transparent)]
+pub struct ValueRef {
+    nodes: [Node],
+}
+
+impl Tape {
+    pub fn parse(json: &str) -> Result<Tape, ParseError> {
+        Tape::parse_slice(json.as_bytes())
+    }
+
+    pub fn parse_slice(json: &[u8]) -> Result<Tape, ParseError> {
+        Tape::parse_document(Parser::new(json))
+    }
+
+    // 磁带保留对象中重复的每个成员；DuplicateKeys::Error时报告错误，
+    // FirstWins时len、members和get只看第一个，其余策略只看最后一个，to_owned按策略合并
+    pub fn parse_with_options(json: &str, options: ParseOptions) -> Result<Tape, ParseError> {
+        Tape::parse_document(Parser::new(json.as_bytes()).with_options(options))
+    }
+
+    fn parse_document(mut parser: Parser) -> Result<Tape, ParseError> {
+        let policy = parser.options().duplicate_keys;
+        let mut nodes = Vec::new();
+        let mut strings = String::new();
+        let mut numbers = Vec::new();
+        let mut children = Vec::new();
+        // 尚未结束的容器的位置，以及它的子节点在positions中的起点；Error策略下还记录每个对象中已出现的键
+        let mut open: Vec<(usize, usize, BTreeSet<String>)> = Vec::new();
+        // 尚未结束的容器的子节点位置：数组的元素，对象的Key节点
+        let mut positions: Vec<u32> = Vec::new();
+        while let Some(event) = parser.next_event()? {
+            if let Some(&(i, _, _)) = open.last() {
+                match (&nodes[i], &event) {
+                    (_, Event::EndArray | Event::EndObject) => {}
+                    (Node::Array(..), _) | (Node::Object(..), Event::Key(_)) => {
+                        positions.push((nodes.len() - i) as u32)
+                    }
+                    _ => {}
+                }
+            }
+            let node = match event {
+                Event::StartArray | Event::StartObject => {
+                    open.push((nodes.len(), positions.len(), BTreeSet::new()));
+                    match event {
+                        Event::StartArray => Node::Array(0, 0, 0),
+                        _ => Node::Object(0, 0, 0),
+                    }
+                }
+                Event::EndArray | Event::EndObject => {
+                    let (i, first, _) = open.pop().unwrap();
+                    let start = children.len() as u32;
+                    let end = nodes.len();
+                    match &nodes[i] {
+                        Node::Array(..) => children.extend(positions.drain(first..)),
+                        _ => {
+                            let (ordered, sorted) = select_members(&nodes[i..], &strings, &positions[first..], policy);
+                            positions.truncate(first);
+                            children.extend(ordered);
+                            children.extend(sorted);
+                        }
+                    }
+                    if children.len() > u32::MAX as usize {
+                        return Err(parser.error(ParseErrorKind::DocumentTooLarge));
+                    }
+                    if let Node::Array(len, size, offset) | Node::Object(len, size, offset) = &mut nodes[i] {
+                        *len = match event {
+                            Event::EndArray => children.len() as u32 - start,
+                            _ => (children.len() as u32 - start) / 2,
+                        };
+                        *size = (end - i) as u32;
+                        *offset = start;
+                    }
+                    continue;
+                }
+                Event::Key(key) => {
+                    if policy == DuplicateKeys::Error
+                        && let Some((_, _, keys)) = open.last_mut()
+                        && !keys.insert(key.to_string())
+                    {
+                        return Err(parser.error_at(parser.key_offset(), ParseErrorKind::DuplicateKey));
+                    }
+                    let start = strings.len() as u32;
+                    strings.push_str(key);
+                    Node::Key(start, strings.len() as u32)
+                }
+                Event::String(s) => {
+                    let start = strings.len() as u32;
+                    strings.push_str(s);
+                    Node::String(start, strings.len() as u32)
+                }
+                Event::Number(n) => {
+                    numbers.push(n);
+                    Node::Number((numbers.len() - 1) as u32)
+                }
+                Event::Bool(b) => Node::Bool(b),
+                Event::Null => Node::Null,
+            };
+            // 节点数、数字个数和字符串的总长度都必须能用u32表示
+            if nodes.len() >= u32::MAX as usize
+                || numbers.len() > u32::MAX as usize
+                || strings.len() > u32::MAX as usize
+            {
+                return Err(parser.error(ParseErrorKind::DocumentTooLarge));
+            }
+            nodes.push(node);
+        }
+        if nodes.is_empty() {
+            return Err(parser.error(ParseErrorKind::ExpectValue));
+        }
+        nodes.push(Node::End(Box::new(Storage {
+            strings,
+            numbers,
+            children,
+            policy,
+        })));
+        Ok(Tape { nodes })
+    }
+
+    pub fn root(&self) -> &ValueRef {
+        ValueRef::new(&self.nodes)
+    }
+}
+
+// 对象中每个不同的键按策略选中的成员：FirstWins取第一个，其余策略取最后一个。
+// 返回这些成员的Key节点位置，分别按键第一次出现的顺序和按键排序
+fn select_members(object: &[Node], strings: &str, keys: &[u32], policy: DuplicateKeys) -> (Vec<u32>, Vec<u32>) {
+    let key = |p: u32| match object[p as usize] {
+        Node::Key(start, end) => &strings[start as usize..end as usize],
+        _ => unreachable!("object members start with a key"),
+    };
+    // 稳定排序，同一个键的成员仍按文档顺序排列
+    let mut sorted = keys.to_vec();
+    sorted.sort_by_key(|&p| key(p));
+    // 每个键第一次出现的位置和选中的位置
+    let mut selected: Vec<(u32, u32)> = Vec::new();
+    for group in sorted.chunk_by(|&a, &b| key(a) == key(b)) {
+        let chosen = match policy {
+            DuplicateKeys::FirstWins => group[0],
+            _ => group[group.len() - 1],
+        };
+        selected.push((group[0], chosen));
+    }
+    let sorted = selected.iter().map(|&(_, chosen)| chosen).collect();
+    selected.sort_unstable_by_key(|&(first, _)| first);
+    (selected.into_iter().map(|(_, chosen)| chosen).collect(), sorted)
+}
+
+impl Deref for Tape {
+    type Target = ValueRef;
+
+    fn deref(&self) -> &ValueRef {
+        self.root()
+    }
+}
+
+impl ValueRef {
+    fn new(nodes: &[Node]) -> &ValueRef {
+        // SAFETY: ValueRef是repr(transparent)的[Node]，两者的布局和指针元数据相同
+        unsafe { &*(nodes as *const [Node] as *const ValueRef) }
+    }
+
+    fn storage(&self) -> &Storage {
+        match self.nodes.last() {
+            Some(Node::End(storage)) => storage,
+            _ => unreachable!("a tape always ends with Node::End"),
+        }
+    }
+
+    fn str_at(&self, start: u32, end: u32) -> &str {
+        &self.storage().strings[start as usize..end as usize]
+    }
+
+    // 从第i个节点开始的值
+    fn at(&self, i: usize) -> &ValueRef {
+        ValueRef::new(&self.nodes[i..])
+    }
+
+    fn key_at(&self, i: usize) -> &str {
+        match self.nodes[i] {
+            Node::Key(start, end) => self.str_at(start, end),
+            _ => unreachable!("object members start with a key"),
+        }
+    }
+
+    // 数组元素或对象成员的Key节点的位置；对象的成员按键第一次出现的顺序排列
+    fn children(&self) -> &[u32] {
+        match self.nodes[0] {
+            Node::Array(len, _, start) | Node::Object(len, _, start) => {
+                &self.storage().children[start as usize..start as usize + len as usize]
+            }
+            _ => &[],
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.nodes[0], Node::Null)
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self.nodes[0], Node::Array(..))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self.nodes[0], Node::Object(..))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.nodes[0] {
+            Node::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self.nodes[0] {
+            Node::Number(i) => Some(&self.storage().numbers[i as usize]),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.as_i64()
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number()?.as_f64()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self.nodes[0] {
+            Node::String(start, end) => Some(self.str_at(start, end)),
+            _ => None,
+        }
+    }
+
+    // 数组的元素个数或对象中不同键的个数，其他值为0
+    pub fn len(&self) -> usize {
+        match self.nodes[0] {
+            Node::Array(len, ..) | Node::Object(len, ..) => len as usize,
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 数组的元素；不是数组时为空
+    pub fn iter(&self) -> impl Iterator<Item = &ValueRef> {
+        let elements = if self.is_array() { self.children() } else { &[] };
+        elements.iter().map(|&i| self.at(i as usize))
+    }
+
+    // 对象的成员，按键第一次出现的顺序，重复的键只出现一次，值与get相同；不是对象时为空
+    pub fn members(&self) -> impl Iterator<Item = (&str, &ValueRef)> {
+        let keys = if self.is_object() { self.children() } else { &[] };
+        keys.iter().map(|&i| (self.key_at(i as usize), self.at(i as usize + 1)))
+    }
+
+    // 按键二分查找对象的成员
+    pub fn get(&self, key: &str) -> Option<&ValueRef> {
+        let Node::Object(len, _, start) = self.nodes[0] else {
+            return None;
+        };
+        let (len, start) = (len as usize, start as usize);
+        let sorted = &self.storage().children[start + len..start + 2 * len];
+        let found = sorted.binary_search_by(|&i| self.key_at(i as usize).cmp(key)).ok()?;
+        Some(self.at(sorted[found] as usize + 1))
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&ValueRef> {
+        if !self.is_array() {
+            return None;
+        }
+        self.children().get(index).map(|&i| self.at(i as usize))
+    }
+
+    // 复制为Value，重复的键按解析时的策略合并
+    pub fn to_owned(&self) -> Value {
+        Value::from_events(self.events(), self.storage().policy)
+    }
+
+    pub fn to_string_with_options(&self, options: WriteOptions) -> Result<String, WriteError> {
+        Value::stringify_events(self.events(), &options)
+    }
+
+    // 按文档顺序生成事件，待处理的值和容器的结束事件放在显式的栈中。
+    // 重复的键按解析时的策略处理，与Value的结果一致：每个键留在第一次出现的位置，
+    // 值与members相同，只有CollectAll把所有值收集为数组
+    fn events(&self) -> impl Iterator<Item = Event<'_>> {
+        let storage = self.storage();
+        let mut pending = vec![Pending::Value(0)];
+        std::iter::from_fn(move || {
+            let i = match pending.pop()? {
+                Pending::Event(event) => return Some(event),
+                Pending::Value(i) => i,
+            };
+            let event = match self.nodes[i] {
+                Node::Null => Event::Null,
+                Node::Bool(b) => Event::Bool(b),
+                Node::Number(n) => Event::Number(storage.numbers[n as usize].clone()),
+                Node::String(start, end) => Event::String(self.str_at(start, end)),
+                Node::Array(..) => {
+                    pending.push(Pending::Event(Event::EndArray));
+                    let elements = self.at(i).children();
+                    pending.extend(elements.iter().rev().map(|&j| Pending::Value(i + j as usize)));
+                    Event::StartArray
+                }
+                Node::Object(len, size, _) if storage.policy == DuplicateKeys::CollectAll => {
+                    // 按第一次出现的顺序分组同一个键的所有值
+                    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+                    let mut index: HashMap<&str, usize> = HashMap::new();
+                    let mut j = i + 1;
+                    while j < i + size as usize {
+                        let key = self.key_at(j);
+                        let group = *index.entry(key).or_insert_with(|| {
+                            groups.push((key, Vec::new()));
+                            groups.len() - 1
+                        });
+                        groups[group].1.push(j + 1);
+                        j += 1 + self.nodes[j + 1].size();
+                    }
+                    debug_assert_eq!(groups.len(), len as usize);
+                    pending.push(Pending::Event(Event::EndObject));
+                    for (key, values) in groups.into_iter().rev() {
+                        if values.len() > 1 {
+                            pending.push(Pending::Event(Event::EndArray));
+                            pending.extend(values.into_iter().rev().map(Pending::Value));
+                            pending.push(Pending::Event(Event::StartArray));
+                        } else {
+                            pending.push(Pending::Value(values[0]));
+                        }
+                        pending.push(Pending::Event(Event::Key(key)));
+                    }
+                    Event::StartObject
+                }
+                Node::Object(..) => {
+                    pending.push(Pending::Event(Event::EndObject));
+                    for &j in self.at(i).children().iter().rev() {
+                        let j = i + j as usize;
+                        pending.push(Pending::Value(j + 1));
+                        pending.push(Pending::Event(Event::Key(self.key_at(j))));
+                    }
+                    Event::StartObject
+                }
+                Node::Key(..) | Node::End(..) => unreachable!("events start at a value"),
+            };
+            Some(event)
+        })
+    }
+}
+
+// 生成事件时尚未处理的值（节点的位置）或容器的结束事件
+enum Pending<'a> {
+    Value(usize),
+    Event(Event<'a>),
+}
+
+impl Index<&str> for ValueRef {
+    type Output = ValueRef;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        if !self.is_object() {
+            panic!("Only objects can be accessed by key")
+        }
+        self.get(key).expect("key not found in object")
+    }
+}
+
+impl Index<usize> for ValueRef {
+    type Output = ValueRef;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if !self.is_array() {
+            panic!("Only arrays can be accessed by index")
+        }
+        self.get_index(index).expect("index out of bounds")
+    }
+}
+
+impl std::fmt::Display for ValueRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_string_with_options(WriteOptions::default()) {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl std::fmt::Debug for ValueRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ValueRef").field(&format_args!("{self}")).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tape_accessors() {
+        assert_eq!(std::mem::size_of::<Node>(), 16);
+        let json = r#"{"name": "kjson", "list": [1, -2, 2.5, true, null, "x\n"], "nested": {"a": {"c": []}}, "e": {}}"#;
+        let tape = Tape::parse(json).unwrap();
+        assert!(tape.is_object());
+        assert_eq!(tape.len(), 4);
+        assert_eq!(tape["name"].as_str(), Some("kjson"));
+        assert_eq!(tape["list"].len(), 6);
+        assert_eq!(tape["list"][0].as_i64(), Some(1));
+        assert_eq!(tape["list"][1].as_u64(), None);
+        assert_eq!(tape["list"][2].as_f64(), Some(2.5));
+        assert_eq!(tape["list"][3].as_bool(), Some(true));
+        assert!(tape["list"][4].is_null());
+        assert_eq!(tape["list"][5].as_str(), Some("x\n"));
+        assert!(tape["nested"]["a"]["c"].is_array());
+        assert!(tape["nested"]["a"]["c"].is_empty());
+        assert!(tape["e"].is_empty());
+        assert!(tape.get("missing").is_none());
+        assert!(tape["list"].get_index(6).is_none());
+        assert!(tape["name"].get("a").is_none());
+
+        let keys: Vec<&str> = tape.members().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["name", "list", "nested", "e"]);
+        let numbers: Vec<i64> = tape["list"].iter().filter_map(|v| v.as_i64()).collect();
+        assert_eq!(numbers, [1, -2]);
+        assert_eq!(tape["name"].iter().count(), 0);
+    }
+
+    #[test]
+    fn tape_lookup() {
+        let json = format!(
+            "{{{}}}",
+            (0..1000)
+                .map(|i| format!(r#""k{i}": [{i}, {{"v": {i}}}]"#))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let tape = Tape::parse(&json).unwrap();
+        assert_eq!(tape.len(), 1000);
+        for i in (0..1000).step_by(37) {
+            let member = &tape[format!("k{i}").as_str()];
+            assert_eq!((member[0].as_i64(), member[1]["v"].as_i64()), (Some(i), Some(i)));
+        }
+        assert!(tape.get("k1000").is_none());
+        let (key, value) = tape.members().nth(999).unwrap();
+        assert_eq!((key, value[0].as_i64()), ("k999", Some(999)));
+
+        let array = Tape::parse(&format!(
+            "[{}]",
+            (0..1000).map(|i| format!("[{i}]")).collect::<Vec<_>>().join(",")
+        ))
+        .unwrap();
+        assert_eq!(array[999][0].as_i64(), Some(999));
+        assert!(array.get_index(1000).is_none());
+        assert!(tape.get_index(0).is_none());
+    }
+
+    #[test]
+    fn tape_to_owned() {
+        let json = r#"{"a": [1, 2.5, "x\ty", {"c": null}], "d": {"e": true}, "k": "v"}"#;
+        let tape = Tape::parse(json).unwrap();
+        let value = Value::parse(json).unwrap();
+        assert_eq!(tape.to_owned(), value);
+        assert_eq!(tape["a"].to_owned(), value["a"]);
+        assert_eq!(tape["a"][3].to_owned(), value["a"][3]);
+        assert_eq!(tape.to_string(), value.to_string());
+        assert_eq!(tape["d"].to_string(), r#"{"e":true}"#);
+        assert_eq!(Tape::parse("1").unwrap().to_owned(), Value::parse("1").unwrap());
+    }
+
+    #[test]
+    fn tape_duplicate_keys() {
+        let json = r#"{"a": 1, "a": "x", "a": [2]}"#;
+        let parse = |duplicate_keys| {
+            let options = ParseOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            Tape::parse_with_options(json, options)
+        };
+        let tape = parse(DuplicateKeys::LastWins).unwrap();
+        assert_eq!(tape.len(), 1);
+        assert_eq!(tape["a"].to_owned(), Value::parse("[2]").unwrap());
+        let tape = parse(DuplicateKeys::FirstWins).unwrap();
+        assert_eq!(tape["a"].as_i64(), Some(1));
+        let members: Vec<(&str, Option<i64>)> = tape.members().map(|(k, v)| (k, v.as_i64())).collect();
+        assert_eq!((tape.len(), members), (1, vec![("a", Some(1))]));
+        assert_eq!(tape.to_owned(), Value::parse(r#"{"a": 1}"#).unwrap());
+        let tape = parse(DuplicateKeys::CollectAll).unwrap();
+        assert_eq!(tape.to_owned(), Value::parse(r#"{"a": [1, "x", [2]]}"#).unwrap());
+        let e = parse(DuplicateKeys::Error).err().unwrap();
+        assert_eq!((e.kind(), e.offset()), (ParseErrorKind::DuplicateKey, 9));
+
+        // 输出与按同样策略解析得到的Value一致
+        let json = r#"{"a": 1, "b": {"c": 1, "c": [2]}, "a": 2, "d": null, "a": [3]}"#;
+        for duplicate_keys in [
+            DuplicateKeys::LastWins,
+            DuplicateKeys::FirstWins,
+            DuplicateKeys::CollectAll,
+        ] {
+            let options = || ParseOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            let tape = Tape::parse_with_options(json, options()).unwrap();
+            let value = Value::parse_with_options(json, options()).unwrap();
+            assert_eq!(tape.to_string(), value.to_string(), "{duplicate_keys:?}");
+            assert_eq!(tape.to_owned(), value);
+        }
+        assert_eq!(Tape::parse(r#"{"a":1,"a":2}"#).unwrap().to_string(), r#"{"a":2}"#);
+
+        // len、members和get都按策略合并重复的键，members保持键第一次出现的顺序
+        for duplicate_keys in [DuplicateKeys::LastWins, DuplicateKeys::FirstWins] {
+            let options = || ParseOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            let tape = Tape::parse_with_options(json, options()).unwrap();
+            let value = Value::parse_with_options(json, options()).unwrap();
+            assert_eq!(tape.len(), 3);
+            assert_eq!(tape["b"].len(), 1);
+            let members: Vec<(&str, Value)> = tape.members().map(|(k, v)| (k, v.to_owned())).collect();
+            let expected: Vec<(&str, Value)> = ["a", "b", "d"].into_iter().map(|k| (k, value[k].clone())).collect();
+            assert_eq!(members, expected);
+            assert_eq!(tape["a"].to_owned(), value["a"]);
+        }
+    }
+}
@@ -1,18 +1,25 @@
-use crate::context::Context;
-use crate::dict::Dict;
-use crate::error::ParseError;
-use crate::number::Number;
-use crate::stack::Stack;
-use std::ops::{Index, IndexMut};
-
-#[derive(Debug, Clone)]
+use crate::dict::{Dict, Key};
+use crate::error::{ParseError, ParseErrorKind, PathSegment, WriteError};
+use crate::handler::Handler;
+use crate::number::{Number, write_float};
+use crate::options::{DuplicateKeys, ParseOptions, WriteOptions};
+use crate::parser::{Event, Parser};
+use std::borrow::Borrow;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::ops::{ControlFlow, Index, IndexMut};
+
+// Value::Object使用的对象类型，后端由cargo feature决定
+pub type Map = Dict<String, Value>;
+
+#[derive(Debug)]
 pub enum Value {
     Null,
     Bool(bool),
     Number(Number),
     String(String),
     Array(Vec<Value>),
-    Object(Dict<String, Value>),
+    Object(Map),
 }
 
 impl Index<&str> for Value {
@@ -59,391 +66,527 @@ impl IndexMut<usize> for Value {
     }
 }
 
+// 按默认的WriteOptions输出，NaN和±Infinity写成null
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", Value::stringify_value(self))
+        match Value::stringify_events(Events::for_output(self), &WriteOptions::default()) {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(std::fmt::Error),
+        }
     }
 }
 
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
-            (Self::Number(l0), Self::Number(r0)) => l0 == r0,
-            (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Array(l0), Self::Array(r0)) => {
-                let mut result = true;
-                if l0.len() != r0.len() {
-                    result = false;
-                } else {
-                    for i in 0..l0.len() {
-                        result = result && (l0[i] == r0[i]);
-                    }
-                }
-                result
-            }
-            (Self::Object(l0), Self::Object(r0)) => l0 == r0,
-            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
-        }
+        tree_eq(self, other)
     }
 }
 
 impl Eq for Value {}
 
+// Clone、PartialEq和序列化都不使用递归，任意深的文档也不会栈溢出
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        Value::from_events(self.events(), DuplicateKeys::LastWins)
+    }
+}
+
 impl Value {
+    // 不使用递归地释放这个值。Value没有自定义Drop，变体中的数据可以直接移出；
+    // 代价是直接drop一个嵌套极深的值会递归释放，可能栈溢出，这时改用drop_deep
+    pub fn drop_deep(mut self) {
+        drop_tree(&mut self);
+    }
+
     pub fn parse(json: &str) -> Result<Value, ParseError> {
         Value::parse_slice(json.as_bytes())
     }
 
     pub fn parse_slice(json: &[u8]) -> Result<Value, ParseError> {
-        let mut c: Context = Context::new(json);
-        Value::parse_whitespace(&mut c).unwrap();
-        match Value::parse_value(&mut c) {
-            Ok(v) => {
-                Value::parse_whitespace(&mut c).unwrap();
-                if c.bytes.is_empty() {
-                    Ok(v)
-                } else {
-                    Err(ParseError::RootNotSingular)
+        Value::parse_document(Parser::new(json))
+    }
+
+    pub fn parse_with_options(json: &str, options: ParseOptions) -> Result<Value, ParseError> {
+        Value::parse_document(Parser::new(json.as_bytes()).with_options(options))
+    }
+
+    // 边读边解析，不必先把整个输入读入内存
+    pub fn from_reader<R: Read>(reader: R) -> Result<Value, ParseError> {
+        Value::parse_document(Parser::from_reader(reader))
+    }
+
+    pub fn from_reader_with_options<R: Read>(reader: R, options: ParseOptions) -> Result<Value, ParseError> {
+        Value::parse_document(Parser::from_reader(reader).with_options(options))
+    }
+
+    // 不构建Value，把解析事件推送给handler。整个文档解析完毕返回Continue，被handler中止则返回Break
+    pub fn parse_with_handler<H: Handler + ?Sized>(json: &str, handler: &mut H) -> Result<ControlFlow<()>, ParseError> {
+        Parser::new(json.as_bytes()).parse_with(handler)
+    }
+
+    pub fn from_reader_with_handler<R: Read, H: Handler + ?Sized>(
+        reader: R,
+        handler: &mut H,
+    ) -> Result<ControlFlow<()>, ParseError> {
+        Parser::from_reader(reader).parse_with(handler)
+    }
+
+    pub(crate) fn parse_document(mut parser: Parser) -> Result<Value, ParseError> {
+        let v = Value::from_parser(&mut parser)?;
+        match parser.next_event()? {
+            None => Ok(v),
+            Some(_) => Err(parser.error(ParseErrorKind::RootNotSingular)),
+        }
+    }
+
+    // 由一个完整值的事件流构建Value，重复的键按policy合并
+    pub(crate) fn from_events<'v>(events: impl Iterator<Item = Event<'v>>, policy: DuplicateKeys) -> Value {
+        let mut builder = ValueBuilder::new().with_duplicate_keys(policy);
+        for event in events {
+            let value = match event {
+                Event::StartArray => {
+                    builder.start_array();
+                    continue;
+                }
+                Event::StartObject => {
+                    builder.start_object();
+                    continue;
+                }
+                Event::Key(key) => {
+                    builder.key(key.to_string());
+                    continue;
                 }
+                Event::EndArray | Event::EndObject => builder.end(),
+                Event::Null => builder.value(Value::Null),
+                Event::Bool(b) => builder.value(Value::Bool(b)),
+                Event::Number(n) => builder.value(Value::Number(n)),
+                Event::String(s) => builder.value(Value::String(s.to_string())),
+            };
+            if let Some(value) = value {
+                return value;
             }
-            Err(e) => Err(e),
         }
+        unreachable!("events of a value always end with the value itself")
     }
 
-    fn parse_whitespace(context: &mut Context) -> Result<(), ParseError> {
-        let bytes = context.bytes;
-        for (i, &c) in bytes.iter().enumerate() {
-            if !(c == b' ' || c == b'\t' || c == b'\n' || c == b'\r') {
-                context.bytes = &bytes[i..];
-                return Ok(());
+    // 从事件流中读取下一个完整的值；可以在遍历事件的过程中只构建感兴趣的子树
+    pub fn from_parser(parser: &mut Parser) -> Result<Value, ParseError> {
+        build_from_parser(
+            parser,
+            |parser| parser.owned_string(),
+            |parser| Value::String(parser.owned_string()),
+        )
+    }
+}
+
+// 从事件流构建一个完整的值，key和string分别从解析器取出最近的键和字符串
+pub(crate) fn build_from_parser<'a, V: Node>(
+    parser: &mut Parser<'a>,
+    mut key: impl FnMut(&Parser<'a>) -> V::Key,
+    string: impl Fn(&Parser<'a>) -> V,
+) -> Result<V, ParseError> {
+    let policy = parser.options().duplicate_keys;
+    let mut builder = ValueBuilder::new().with_duplicate_keys(policy);
+    loop {
+        let value = match parser.next_event()? {
+            Some(Event::StartArray) => {
+                builder.start_array();
+                continue;
             }
-        }
-        context.bytes = &context.bytes[context.bytes.len()..];
-        Ok(())
-    }
-
-    fn parse_value(context: &mut Context) -> Result<Value, ParseError> {
-        let bytes = context.bytes;
-        match bytes.first() {
-            Some(byte) => match *byte {
-                b'n' | b't' | b'f' => Value::parse_literal(context),
-                b'"' => Value::parse_string(context),
-                b'[' => Value::parse_array(context),
-                b'{' => Value::parse_object(context),
-                _ => Value::parse_number(context),
+            Some(Event::StartObject) => {
+                builder.start_object();
+                continue;
+            }
+            Some(Event::Key(_)) if builder.depth() > 0 => {
+                let key = key(parser);
+                if policy == DuplicateKeys::Error && builder.contains_key(key.borrow()) {
+                    return Err(parser.error_at(parser.key_offset(), ParseErrorKind::DuplicateKey));
+                }
+                builder.key(key);
+                continue;
+            }
+            Some(Event::EndArray | Event::EndObject) if builder.depth() > 0 => match builder.end() {
+                Some(v) => return Ok(v),
+                None => continue,
             },
-            None => Err(ParseError::ExpectValue),
+            Some(Event::Null) => V::from_scalar(NodeRef::Null),
+            Some(Event::Bool(b)) => V::from_scalar(NodeRef::Bool(b)),
+            Some(Event::Number(n)) => V::from_scalar(NodeRef::Number(&n)),
+            Some(Event::String(_)) => string(parser),
+            Some(Event::Key(_) | Event::EndArray | Event::EndObject) | None => {
+                return Err(parser.error(ParseErrorKind::ExpectValue));
+            }
+        };
+        if let Some(v) = builder.value(value) {
+            return Ok(v);
         }
     }
+}
 
-    fn check_literal(context: &mut Context, literal: &[u8]) -> Result<(), ParseError> {
-        let bytes = context.bytes;
-        if bytes.len() < literal.len() || &bytes[0..literal.len()] != literal {
-            return Err(ParseError::InvalidValue);
-        }
-        context.bytes = &bytes[literal.len()..];
-        Ok(())
+// Value和BorrowedValue共用的树操作：构建、遍历、比较和释放都不使用递归，
+// 任意深的文档也不会栈溢出
+pub(crate) trait Node: Sized {
+    type Key: Key + Borrow<str> + Default;
+
+    fn from_array(arr: Vec<Self>) -> Self;
+    fn from_object(object: Dict<Self::Key, Self>) -> Self;
+    // 只用于Null、Bool和Number
+    fn from_scalar(scalar: NodeRef<'_, Self>) -> Self;
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>>;
+    fn as_node(&self) -> NodeRef<'_, Self>;
+    // 把数组元素或对象成员的值移到out中
+    fn take_children(&mut self, out: &mut Vec<Self>);
+}
+
+pub(crate) enum NodeRef<'v, V: Node> {
+    Null,
+    Bool(bool),
+    Number(&'v Number),
+    String(&'v str),
+    Array(&'v [V]),
+    Object(&'v Dict<V::Key, V>),
+}
+
+impl Node for Value {
+    type Key = String;
+
+    fn from_array(arr: Vec<Self>) -> Self {
+        Value::Array(arr)
     }
 
-    fn parse_literal(context: &mut Context) -> Result<Value, ParseError> {
-        let bytes = context.bytes;
-        match *bytes.first().unwrap() {
-            b'n' => Value::check_literal(context, "null".as_bytes()).map(|_| Value::Null),
-            b't' => Value::check_literal(context, "true".as_bytes()).map(|_| Value::Bool(true)),
-            b'f' => Value::check_literal(context, "false".as_bytes()).map(|_| Value::Bool(false)),
-            _ => Err(ParseError::InvalidValue),
-        }
+    fn from_object(object: Dict<String, Self>) -> Self {
+        Value::Object(object)
     }
 
-    fn skip_following_digits(bytes: &[u8], start: usize) -> usize {
-        if start >= bytes.len() {
-            return 0_usize;
+    fn from_scalar(scalar: NodeRef<'_, Self>) -> Self {
+        match scalar {
+            NodeRef::Bool(b) => Value::Bool(b),
+            NodeRef::Number(n) => Value::Number(n.clone()),
+            _ => Value::Null,
         }
-        let mut count: usize = 0;
-        for &b in bytes[start..].iter() {
-            if !(b.is_ascii_digit()) {
-                break;
-            }
-            count += 1;
-        }
-        count
     }
 
-    fn parse_number(context: &mut Context) -> Result<Value, ParseError> {
-        let bytes = context.bytes;
-        let mut index_end: usize = 0;
-        let mut is_float: bool = false;
-        // 整数部分
-        if *bytes.first().unwrap() == b'-' {
-            index_end += 1;
-        }
-        {
-            let len_int = Value::skip_following_digits(bytes, index_end);
-            if len_int == 0 {
-                return Err(ParseError::InvalidValue);
-            }
-            if bytes[index_end] == b'0' && len_int > 1 {
-                return Err(ParseError::RootNotSingular);
-            }
-            index_end += len_int;
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match self {
+            Value::Array(arr) => Some(arr),
+            _ => None,
         }
+    }
 
-        // 小数部分
-        if index_end < bytes.len() && bytes[index_end] == b'.' {
-            index_end += 1;
-            is_float = true;
-            let len_int = Value::skip_following_digits(bytes, index_end);
-            if len_int == 0 {
-                return Err(ParseError::InvalidValue);
-            }
-            index_end += len_int;
+    fn as_node(&self) -> NodeRef<'_, Self> {
+        match self {
+            Value::Null => NodeRef::Null,
+            Value::Bool(b) => NodeRef::Bool(*b),
+            Value::Number(n) => NodeRef::Number(n),
+            Value::String(s) => NodeRef::String(s),
+            Value::Array(arr) => NodeRef::Array(arr),
+            Value::Object(object) => NodeRef::Object(object),
         }
+    }
 
-        // 指数部分
-        if index_end < bytes.len() && (bytes[index_end] == b'e' || bytes[index_end] == b'E') {
-            index_end += 1;
-            is_float = true;
-            // 正负号
-            if index_end < bytes.len() && (bytes[index_end] == b'+' || bytes[index_end] == b'-') {
-                index_end += 1;
-            }
-            let len_int = Value::skip_following_digits(bytes, index_end);
-            if len_int == 0 {
-                return Err(ParseError::InvalidValue);
-            }
-            index_end += len_int;
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Value::Array(arr) => out.append(arr),
+            Value::Object(object) => out.extend(std::mem::take(object).into_iter().map(|(_, v)| v)),
+            _ => {}
         }
+    }
+}
 
-        // 转换为二进制返回
-        context.bytes = &bytes[index_end..];
-        let number_str = std::str::from_utf8(&bytes[0..index_end]).unwrap();
-        if !is_float {
-            if let Ok(num) = number_str.parse::<i64>() {
-                return Ok(Value::Number(Number::Int(num)));
-            }
-        }
-        match number_str.parse::<f64>() {
-            Ok(num) => {
-                if num.is_finite() {
-                    Ok(Value::Number(Number::Float(num)))
-                } else {
-                    Err(ParseError::NumberTooBig)
+// 对象的比较与成员顺序无关，因此逐对比较而不是比较事件流
+pub(crate) fn tree_eq<V: Node>(a: &V, b: &V) -> bool {
+    let mut stack = vec![(a, b)];
+    while let Some((a, b)) = stack.pop() {
+        match (a.as_node(), b.as_node()) {
+            (NodeRef::Null, NodeRef::Null) => {}
+            (NodeRef::Bool(a), NodeRef::Bool(b)) if a == b => {}
+            (NodeRef::Number(a), NodeRef::Number(b)) if a == b => {}
+            (NodeRef::String(a), NodeRef::String(b)) if a == b => {}
+            (NodeRef::Array(a), NodeRef::Array(b)) if a.len() == b.len() => stack.extend(a.iter().zip(b)),
+            (NodeRef::Object(a), NodeRef::Object(b)) if a.len() == b.len() => {
+                for (key, value) in a.iter() {
+                    match b.get(key.borrow()) {
+                        Some(other) => stack.push((value, other)),
+                        None => return false,
+                    }
                 }
             }
-            Err(_) => Err(ParseError::NumberTooBig),
+            _ => return false,
         }
     }
+    true
+}
+
+// 把子节点移到堆上的栈中逐个释放，使每个子节点在释放时都已没有子节点
+pub(crate) fn drop_tree<V: Node>(value: &mut V) {
+    let mut stack = Vec::new();
+    value.take_children(&mut stack);
+    while let Some(mut value) = stack.pop() {
+        value.take_children(&mut stack);
+    }
+}
 
-    fn hex4_to_u32(hex4: &[u8]) -> Option<u32> {
-        assert_eq!(hex4.len(), 4);
-        let mut value = 0u32;
-        for &b in hex4 {
-            let digit = match b {
-                b'0'..=b'9' => b - b'0',
-                b'a'..=b'f' => b - b'a' + 10,
-                b'A'..=b'F' => b - b'A' + 10,
-                _ => return None, // 非法字符
+// 构建Value时尚未完成的容器；对象同时保存当前成员的键，
+// 以及CollectAll策略下值已被收集为数组的键
+enum Frame<V: Node> {
+    Array(Vec<V>),
+    Object(Dict<V::Key, V>, V::Key, BTreeSet<V::Key>),
+}
+
+// 由事件逐步构建Value，用显式的栈代替递归；拉取式解析器和增量解析器共用
+pub(crate) struct ValueBuilder<V: Node = Value> {
+    frames: Vec<Frame<V>>,
+    duplicate_keys: DuplicateKeys,
+}
+
+// 出错时已构建的部分可能嵌套很深，逐层释放
+impl<V: Node> Drop for ValueBuilder<V> {
+    fn drop(&mut self) {
+        while let Some(frame) = self.frames.pop() {
+            let mut value = match frame {
+                Frame::Array(arr) => V::from_array(arr),
+                Frame::Object(object, ..) => V::from_object(object),
             };
+            drop_tree(&mut value);
+        }
+    }
+}
 
-            value = (value << 4) | u32::from(digit);
+impl<V: Node> ValueBuilder<V> {
+    pub fn new() -> Self {
+        Self {
+            frames: Vec::new(),
+            duplicate_keys: DuplicateKeys::LastWins,
         }
-        Some(value)
     }
 
-    fn encode_utf8(stack: &mut Stack<u8>, c: u32) -> Result<(), ParseError> {
-        if let Some(ch) = char::from_u32(c) {
-            let mut buf = [0; 4]; // UTF-8 最多需要 4 个字节
-            let bytes = ch.encode_utf8(&mut buf);
-            let utf8_bytes: &[u8] = bytes.as_bytes(); // 获取 &[u8]
-            stack.push_bytes(utf8_bytes);
-            Ok(())
-        } else {
-            Err(ParseError::InvalidUnicodeSurrogate)
+    // 重复的键按policy合并；Error策略需要调用者在key之前用contains_key检查
+    pub fn with_duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    // 最内层容器中已有的元素或成员个数
+    pub fn len(&self) -> usize {
+        match self.frames.last() {
+            Some(Frame::Array(arr)) => arr.len(),
+            Some(Frame::Object(object, ..)) => object.len(),
+            None => 0,
         }
     }
 
-    fn parse_string_raw(context: &mut Context) -> Result<String, ParseError> {
-        if context.bytes.len() < 2 || *context.bytes.first().unwrap() != b'"' {
-            return Err(ParseError::MissQuotationMark);
+    pub fn in_object(&self) -> bool {
+        matches!(self.frames.last(), Some(Frame::Object(..)))
+    }
+
+    // 最内层的对象中是否已有这个键
+    pub fn contains_key(&self, key: &str) -> bool {
+        matches!(self.frames.last(), Some(Frame::Object(object, ..)) if object.contains_key(key))
+    }
+
+    pub fn start_array(&mut self) {
+        self.frames.push(Frame::Array(Vec::new()));
+    }
+
+    pub fn start_object(&mut self) {
+        self.frames
+            .push(Frame::Object(Dict::new(), V::Key::default(), BTreeSet::new()));
+    }
+
+    pub fn key(&mut self, key: V::Key) {
+        if let Some(Frame::Object(_, pending, _)) = self.frames.last_mut() {
+            *pending = key;
         }
-        let mut stack: Stack<u8> = Stack::new();
-        let mut quotation_marked: bool = false;
-        let mut i_context = 1;
-        let cur_len = stack.len();
-        while i_context < context.bytes.len() {
-            let b = context.bytes[i_context];
-            match b {
-                b'"' => {
-                    quotation_marked = true;
-                    break;
-                }
-                b'\\' => {
-                    // 处理转义序列
-                    if i_context + 1 < context.bytes.len() {
-                        match context.bytes[i_context + 1] {
-                            b'"' => stack.push_byte(b'\"'),
-                            b'\\' => stack.push_byte(b'\\'),
-                            b'/' => stack.push_byte(b'/'),
-                            b'b' => stack.push_byte(b'\x62'),
-                            b'f' => stack.push_byte(b'\x66'),
-                            b'n' => stack.push_byte(b'\n'),
-                            b'r' => stack.push_byte(b'\r'),
-                            b't' => stack.push_byte(b'\t'),
-                            b'u' => {
-                                if i_context + 6 >= context.bytes.len() {
-                                    return Err(ParseError::InvalidUnicodeHex);
-                                }
-                                match Value::hex4_to_u32(&context.bytes[i_context + 2..i_context + 6]) {
-                                    Some(high_surrogate) => {
-                                        if (0xD800..=0xDBFF).contains(&high_surrogate) {
-                                            // 代码对的高代理项（high surrogate）
-                                            if i_context + 12 < context.bytes.len()
-                                                && (context.bytes[i_context + 6] == b'\\'
-                                                    && context.bytes[i_context + 7] == b'u')
-                                            {
-                                                match Value::hex4_to_u32(&context.bytes[i_context + 8..i_context + 12])
-                                                {
-                                                    Some(low_surrogate) => {
-                                                        if !(0xDC00..=0xDFFF).contains(&low_surrogate) {
-                                                            return Err(ParseError::InvalidUnicodeSurrogate);
-                                                        }
-                                                        if let Err(e) = Value::encode_utf8(
-                                                            &mut stack,
-                                                            0x10000
-                                                                + (high_surrogate - 0xD800) * 0x400
-                                                                + (low_surrogate - 0xDC00),
-                                                        ) {
-                                                            return Err(e);
-                                                        }
-                                                    }
-                                                    None => return Err(ParseError::InvalidUnicodeHex),
-                                                }
-                                                i_context += 10;
-                                            } else {
-                                                return Err(ParseError::InvalidUnicodeSurrogate);
-                                            }
-                                        } else if let Err(e) = Value::encode_utf8(&mut stack, high_surrogate) {
-                                            return Err(e);
-                                        } else {
-                                            i_context += 4;
-                                        }
-                                    }
-                                    None => return Err(ParseError::InvalidUnicodeHex),
-                                }
-                            }
-                            _ => return Err(ParseError::InvalidStringEscape),
+    }
+
+    // 结束最内层的容器；若它就是根值则返回构建完成的值
+    pub fn end(&mut self) -> Option<V> {
+        let value = match self.frames.pop()? {
+            Frame::Array(arr) => V::from_array(arr),
+            Frame::Object(object, ..) => V::from_object(object),
+        };
+        self.value(value)
+    }
+
+    // 把一个完整的值放入所在的容器；没有所在容器时它就是根值，直接返回
+    pub fn value(&mut self, value: V) -> Option<V> {
+        let policy = self.duplicate_keys;
+        match self.frames.last_mut() {
+            None => return Some(value),
+            Some(Frame::Array(arr)) => arr.push(value),
+            Some(Frame::Object(object, key, collected)) => {
+                let key = std::mem::take(key);
+                match (policy, object.get_mut(key.borrow())) {
+                    (DuplicateKeys::FirstWins, Some(_)) => {}
+                    (DuplicateKeys::CollectAll, Some(existing)) if collected.contains::<str>(key.borrow()) => {
+                        if let Some(values) = existing.as_array_mut() {
+                            values.push(value);
                         }
-                        i_context += 2;
                     }
-                }
-                _ => {
-                    if b < 0x20 {
-                        return Err(ParseError::InvalidStringChar);
+                    (DuplicateKeys::CollectAll, Some(existing)) => {
+                        let first = std::mem::replace(existing, V::from_array(Vec::new()));
+                        *existing = V::from_array(vec![first, value]);
+                        collected.insert(key);
+                    }
+                    _ => {
+                        object.insert(key, value);
                     }
-                    stack.push_byte(b);
-                    i_context += 1;
                 }
             }
         }
-        if quotation_marked {
-            context.bytes = &context.bytes[i_context + 1..];
-            Ok(String::from_utf8(stack.pop_bytes(stack.len() - cur_len)).unwrap())
-        } else {
-            Err(ParseError::MissQuotationMark)
-        }
+        None
     }
 
-    fn parse_string(context: &mut Context) -> Result<Value, ParseError> {
-        assert_eq!(*context.bytes.first().unwrap(), b'"');
-        Value::parse_string_raw(context).map(Value::String)
+    // 当前位置在文档中的路径；include_last为false时不包含最内层容器中正在解析的成员
+    pub fn path(&self, include_last: bool) -> Vec<PathSegment> {
+        let n = if include_last {
+            self.frames.len()
+        } else {
+            self.frames.len().saturating_sub(1)
+        };
+        self.frames[..n]
+            .iter()
+            .map(|frame| match frame {
+                Frame::Array(arr) => PathSegment::Index(arr.len()),
+                Frame::Object(_, key, _) => PathSegment::Key(key.borrow().to_string()),
+            })
+            .collect()
     }
+}
 
-    fn parse_array(context: &mut Context) -> Result<Value, ParseError> {
-        assert_eq!(context.step().unwrap(), b'[');
-        Value::parse_whitespace(context).unwrap();
+// 容器中尚未遍历的成员；Sorted是迭代顺序不确定的后端按键排序后的成员
+enum Members<'v, V: Node> {
+    Array(std::slice::Iter<'v, V>),
+    Object(crate::dict::Iter<'v, V::Key, V>),
+    Sorted(std::vec::IntoIter<(&'v V::Key, &'v V)>),
+}
 
-        let mut arr: Vec<Value> = Vec::new();
+// 按文档顺序产生一个值的事件，与解析时的事件流相同；用显式的栈代替递归
+pub(crate) struct Events<'v, V: Node = Value> {
+    pending: Option<&'v V>,
+    stack: Vec<Members<'v, V>>,
+    sort_keys: bool,
+}
 
-        if *context.bytes.first().unwrap() == b']' {
-            context.step();
-            return Ok(Value::Array(arr));
+impl<'v, V: Node> Events<'v, V> {
+    pub fn new(value: &'v V) -> Self {
+        Events {
+            pending: Some(value),
+            stack: Vec::new(),
+            sort_keys: false,
         }
+    }
 
-        loop {
-            match Value::parse_value(context) {
-                Ok(v) => arr.push(v),
-                Err(e) => return Err(e),
-            }
-            Value::parse_whitespace(context).unwrap();
-            let next_byte = context.step();
-            match next_byte {
-                Some(b) => match b {
-                    b',' => Value::parse_whitespace(context).unwrap(),
-                    b']' => return Ok(Value::Array(arr)),
-                    _ => return Err(ParseError::MissCommaOrSquareBracket),
-                },
-                None => return Err(ParseError::MissCommaOrSquareBracket),
-            }
+    // 输出JSON用：迭代顺序不确定的后端按键排序，保证同一个值总是得到同样的文本
+    pub fn for_output(value: &'v V) -> Self {
+        Events {
+            sort_keys: true,
+            ..Events::new(value)
         }
     }
+}
 
-    fn parse_object(context: &mut Context) -> Result<Value, ParseError> {
-        assert_eq!(context.step().unwrap(), b'{');
-        Value::parse_whitespace(context).unwrap();
+impl<'v, V: Node> Iterator for Events<'v, V> {
+    type Item = Event<'v>;
 
-        let mut object: Dict<String, Value> = Dict::new();
-        if *context.bytes.first().unwrap() == b'}' {
-            context.step();
-            return Ok(Value::Object(object));
-        }
-        loop {
-            // parse key
-            if let Ok(str) = Value::parse_string_raw(context) {
-                let key = str;
-                // parse colon(:)
-                Value::parse_whitespace(context).unwrap();
-                if let Some(b':') = context.step() {
-                } else {
-                    return Err(ParseError::MissColon);
-                }
-                // parse value
-                Value::parse_whitespace(context).unwrap();
-                match Value::parse_value(context) {
-                    Ok(v) => {
-                        object.insert(key, v);
+    fn next(&mut self) -> Option<Event<'v>> {
+        if self.pending.is_none() {
+            let member = match self.stack.last_mut()? {
+                Members::Array(members) => match members.next() {
+                    Some(value) => {
+                        self.pending = Some(value);
+                        None
                     }
-                    Err(_) => return Err(ParseError::InvalidValue),
-                }
-                // parse ws [comma | right-curly-brace] ws }
-                Value::parse_whitespace(context).unwrap();
-                match context.step() {
-                    Some(b',') => {
-                        Value::parse_whitespace(context).unwrap();
+                    None => {
+                        self.stack.pop();
+                        return Some(Event::EndArray);
                     }
-                    Some(b'}') => return Ok(Value::Object(object)),
-                    _ => return Err(ParseError::MissCommaOrCurlyBracket),
-                }
-            } else {
-                return Err(ParseError::MissKey);
+                },
+                Members::Object(members) => Some(members.next()),
+                Members::Sorted(members) => Some(members.next()),
+            };
+            if let Some(member) = member {
+                let event = match member {
+                    Some((key, value)) => {
+                        self.pending = Some(value);
+                        Event::Key(key.borrow())
+                    }
+                    None => {
+                        self.stack.pop();
+                        Event::EndObject
+                    }
+                };
+                return Some(event);
             }
         }
+        let event = match self.pending.take()?.as_node() {
+            NodeRef::Null => Event::Null,
+            NodeRef::Bool(b) => Event::Bool(b),
+            NodeRef::Number(n) => Event::Number(n.clone()),
+            NodeRef::String(s) => Event::String(s),
+            NodeRef::Array(arr) => {
+                self.stack.push(Members::Array(arr.iter()));
+                Event::StartArray
+            }
+            NodeRef::Object(object) => {
+                let sorted = if self.sort_keys { object.sorted_entries() } else { None };
+                let members = match sorted {
+                    Some(entries) => Members::Sorted(entries.into_iter()),
+                    None => Members::Object(object.iter()),
+                };
+                self.stack.push(members);
+                Event::StartObject
+            }
+        };
+        Some(event)
     }
 }
 
 impl Value {
-    fn stringify_value(value: &Value) -> String {
-        match value {
-            Value::Null => String::from("null"),
-            Value::Bool(b) => b.to_string(),
-            Value::Number(n) => n.to_string(),
-            Value::String(s) => Value::stringify_string(s),
-            Value::Array(arr) => Value::stringify_array(arr),
-            Value::Object(object) => Value::stringify_object(object),
+    pub(crate) fn events(&self) -> Events<'_> {
+        Events::new(self)
+    }
+
+    pub fn to_string_with_options(&self, options: WriteOptions) -> Result<String, WriteError> {
+        Value::stringify_events(Events::for_output(self), &options)
+    }
+
+    pub(crate) fn stringify_events<'v>(
+        events: impl Iterator<Item = Event<'v>>,
+        options: &WriteOptions,
+    ) -> Result<String, WriteError> {
+        let mut result = String::new();
+        // 上一个输出的是完整的值时，之后的值或键前面需要逗号
+        let mut after_value = false;
+        for event in events {
+            if after_value && !matches!(event, Event::EndArray | Event::EndObject) {
+                result.push(',');
+            }
+            after_value = !matches!(event, Event::StartArray | Event::StartObject | Event::Key(_));
+            match event {
+                Event::Null => result.push_str("null"),
+                Event::Bool(b) => result.push_str(&b.to_string()),
+                Event::Number(Number::Float(n)) => result.push_str(&write_float(n, options)?),
+                Event::Number(n) => result.push_str(&n.to_string()),
+                Event::String(s) => result.push_str(&Value::stringify_string(s)),
+                Event::StartArray => result.push('['),
+                Event::EndArray => result.push(']'),
+                Event::StartObject => result.push('{'),
+                Event::EndObject => result.push('}'),
+                Event::Key(key) => {
+                    result.push_str(&Value::stringify_string(key));
+                    result.push(':');
+                }
+            }
         }
+        Ok(result)
     }
 
-    fn stringify_string(s: &String) -> String {
+    pub(crate) fn stringify_string(s: &str) -> String {
         let mut stack = Vec::new();
         stack.push(b'"');
         for &byte in s.as_bytes().iter() {
@@ -456,11 +599,11 @@ impl Value {
                     stack.push(b'\\');
                     stack.push(b'\\');
                 }
-                b'\x62' => {
+                b'\x08' => {
                     stack.push(b'\\');
                     stack.push(b'b');
                 }
-                b'\x66' => {
+                b'\x0C' => {
                     stack.push(b'\\');
                     stack.push(b'f');
                 }
@@ -491,61 +634,12 @@ impl Value {
 
         std::str::from_utf8(&stack).unwrap().to_string()
     }
-
-    fn stringify_array(arr: &[Value]) -> String {
-        let mut result = String::from("[");
-        match arr.len() {
-            0 => (),
-            1 => result.push_str(&arr.first().unwrap().to_string()),
-            _ => {
-                result.push_str(&arr.first().unwrap().to_string());
-                for v in arr.iter().skip(1) {
-                    result.push(',');
-                    result.push_str(&v.to_string());
-                }
-            }
-        }
-        result.push(']');
-        result
-    }
-
-    fn stringify_object(object: &Dict<String, Value>) -> String {
-        let mut result = String::from("{");
-        match object.len() {
-            0 => (),
-            1 => {
-                let (key, value) = object.first_key_value().unwrap();
-                result.push('"');
-                result.push_str(key);
-                result.push('"');
-                result.push(':');
-                result.push_str(&value.to_string());
-            }
-            _ => {
-                let (key, value) = object.first_key_value().unwrap();
-                result.push('"');
-                result.push_str(key);
-                result.push('"');
-                result.push(':');
-                result.push_str(&value.to_string());
-                for (key, value) in object.iter().skip(1) {
-                    result.push(',');
-                    result.push('"');
-                    result.push_str(key);
-                    result.push('"');
-                    result.push(':');
-                    result.push_str(&value.to_string());
-                }
-            }
-        }
-        result.push('}');
-        result
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::NonFiniteFloats;
 
     #[test]
     fn parse_null() {
@@ -571,91 +665,97 @@ mod tests {
         );
     }
 
+    // 启用arbitrary_precision时数字保存原始文本并按精确的十进制值比较，这里检查它最接近的f64
+    fn parse_float(json: &str) -> Result<Value, ParseError> {
+        Value::parse(json).map(|v| match v {
+            Value::Number(n) if !n.is_integer() => Value::Number(Number::Float(n.as_f64().unwrap())),
+            v => v,
+        })
+    }
+
     #[test]
+    #[allow(clippy::approx_constant, clippy::excessive_precision)]
     fn parse_number() {
-        assert_eq!(Value::parse("0").ok().unwrap(), Value::Number(Number::Int(0)));
-        assert_eq!(Value::parse("-0").ok().unwrap(), Value::Number(Number::Int(0)));
-        assert_eq!(Value::parse("1").ok().unwrap(), Value::Number(Number::Int(1)));
-        assert_eq!(Value::parse("-1").ok().unwrap(), Value::Number(Number::Int(-1)));
-        assert_eq!(Value::parse("-0.0").ok().unwrap(), Value::Number(Number::Float(0.0)));
-        assert_eq!(Value::parse("-1.5").ok().unwrap(), Value::Number(Number::Float(-1.5)));
-        assert_eq!(Value::parse("1.5").ok().unwrap(), Value::Number(Number::Float(1.5)));
-        assert_eq!(
-            Value::parse("3.1415926").ok().unwrap(),
+        assert_eq!(parse_float("0").ok().unwrap(), Value::Number(Number::Int(0)));
+        assert_eq!(parse_float("-0").ok().unwrap(), Value::Number(Number::Int(0)));
+        assert_eq!(parse_float("1").ok().unwrap(), Value::Number(Number::Int(1)));
+        assert_eq!(parse_float("-1").ok().unwrap(), Value::Number(Number::Int(-1)));
+        assert_eq!(parse_float("-0.0").ok().unwrap(), Value::Number(Number::Float(0.0)));
+        assert_eq!(parse_float("-1.5").ok().unwrap(), Value::Number(Number::Float(-1.5)));
+        assert_eq!(parse_float("1.5").ok().unwrap(), Value::Number(Number::Float(1.5)));
+        assert_eq!(
+            parse_float("3.1415926").ok().unwrap(),
             Value::Number(Number::Float(3.1415926))
         );
-        assert_eq!(Value::parse("1E10").ok().unwrap(), Value::Number(Number::Float(1E10)));
-        assert_eq!(Value::parse("1e10").ok().unwrap(), Value::Number(Number::Float(1e10)));
-        assert_eq!(Value::parse("1E+10").ok().unwrap(), Value::Number(Number::Float(1E+10)));
-        assert_eq!(Value::parse("1E-10").ok().unwrap(), Value::Number(Number::Float(1E-10)));
-        assert_eq!(Value::parse("-1E10").ok().unwrap(), Value::Number(Number::Float(-1E10)));
-        assert_eq!(Value::parse("-1e10").ok().unwrap(), Value::Number(Number::Float(-1e10)));
+        assert_eq!(parse_float("1E10").ok().unwrap(), Value::Number(Number::Float(1E10)));
+        assert_eq!(parse_float("1e10").ok().unwrap(), Value::Number(Number::Float(1e10)));
+        assert_eq!(parse_float("1E+10").ok().unwrap(), Value::Number(Number::Float(1E+10)));
+        assert_eq!(parse_float("1E-10").ok().unwrap(), Value::Number(Number::Float(1E-10)));
+        assert_eq!(parse_float("-1E10").ok().unwrap(), Value::Number(Number::Float(-1E10)));
+        assert_eq!(parse_float("-1e10").ok().unwrap(), Value::Number(Number::Float(-1e10)));
         assert_eq!(
-            Value::parse("-1E+10").ok().unwrap(),
+            parse_float("-1E+10").ok().unwrap(),
             Value::Number(Number::Float(-1E+10))
         );
         assert_eq!(
-            Value::parse("-1E-10").ok().unwrap(),
+            parse_float("-1E-10").ok().unwrap(),
             Value::Number(Number::Float(-1E-10))
         );
         assert_eq!(
-            Value::parse("1.234E+10").ok().unwrap(),
+            parse_float("1.234E+10").ok().unwrap(),
             Value::Number(Number::Float(1.234E+10))
         );
         assert_eq!(
-            Value::parse("1.234E-10").ok().unwrap(),
+            parse_float("1.234E-10").ok().unwrap(),
             Value::Number(Number::Float(1.234E-10))
         );
+        assert_eq!(parse_float("1e-10000").ok().unwrap(), Value::Number(Number::Float(0.0)));
         assert_eq!(
-            Value::parse("1e-10000").ok().unwrap(),
-            Value::Number(Number::Float(0.0))
-        );
-        assert_eq!(
-            Value::parse("0.01171875").ok().unwrap(),
+            parse_float("0.01171875").ok().unwrap(),
             Value::Number(Number::Float(0.01171875))
         );
         assert_eq!(
-            Value::parse("2e-1074").ok().unwrap(),
+            parse_float("2e-1074").ok().unwrap(),
             Value::Number(Number::Float(2.0e-1074))
         );
         assert_eq!(
-            Value::parse("2e-1022").ok().unwrap(),
+            parse_float("2e-1022").ok().unwrap(),
             Value::Number(Number::Float(2.0e-1022))
         );
         assert_eq!(
-            Value::parse("1.0000000000000002").ok().unwrap(),
+            parse_float("1.0000000000000002").ok().unwrap(),
             Value::Number(Number::Float(1.0000000000000002))
         ); /* the smallest number > 1 */
         assert_eq!(
-            Value::parse("4.9406564584124654e-324").ok().unwrap(),
+            parse_float("4.9406564584124654e-324").ok().unwrap(),
             Value::Number(Number::Float(4.9406564584124654e-324))
         ); /* minimum denormal */
         assert_eq!(
-            Value::parse("-4.9406564584124654e-324").ok().unwrap(),
+            parse_float("-4.9406564584124654e-324").ok().unwrap(),
             Value::Number(Number::Float(-4.9406564584124654e-324))
         );
         assert_eq!(
-            Value::parse("2.2250738585072009e-308").ok().unwrap(),
+            parse_float("2.2250738585072009e-308").ok().unwrap(),
             Value::Number(Number::Float(2.2250738585072009e-308))
         ); /* Max subnormal double */
         assert_eq!(
-            Value::parse("-2.2250738585072009e-308").ok().unwrap(),
+            parse_float("-2.2250738585072009e-308").ok().unwrap(),
             Value::Number(Number::Float(-2.2250738585072009e-308))
         );
         assert_eq!(
-            Value::parse("2.2250738585072014e-308").ok().unwrap(),
+            parse_float("2.2250738585072014e-308").ok().unwrap(),
             Value::Number(Number::Float(2.2250738585072014e-308))
         ); /* Min normal positive double */
         assert_eq!(
-            Value::parse("-2.2250738585072014e-308").ok().unwrap(),
+            parse_float("-2.2250738585072014e-308").ok().unwrap(),
             Value::Number(Number::Float(-2.2250738585072014e-308))
         );
         assert_eq!(
-            Value::parse("1.7976931348623157e+308").ok().unwrap(),
+            parse_float("1.7976931348623157e+308").ok().unwrap(),
             Value::Number(Number::Float(1.7976931348623157e+308))
         ); /* Max double */
         assert_eq!(
-            Value::parse("-1.7976931348623157e+308").ok().unwrap(),
+            parse_float("-1.7976931348623157e+308").ok().unwrap(),
             Value::Number(Number::Float(-1.7976931348623157e+308))
         );
     }
@@ -673,7 +773,7 @@ mod tests {
         );
         assert_eq!(
             Value::parse(r#""\" \\ / \b \f \n \r \t""#).ok().unwrap(),
-            Value::String("\" \\ / \x62 \x66 \n \r \t".to_string())
+            Value::String("\" \\ / \x08 \x0C \n \r \t".to_string())
         );
         assert_eq!(
             Value::parse(r#""\u0024""#).ok().unwrap(),
@@ -698,6 +798,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn parse_array() {
         let result = Value::parse(r#"[ ]"#);
         assert!(result.is_ok());
@@ -782,194 +883,591 @@ mod tests {
 
     #[test]
     fn parse_expect_value() {
-        assert_eq!(Value::parse("").err().unwrap(), ParseError::ExpectValue);
-        assert_eq!(Value::parse(" \t\r\n\n").err().unwrap(), ParseError::ExpectValue);
+        assert_eq!(Value::parse("").err().unwrap().kind(), ParseErrorKind::ExpectValue);
+        assert_eq!(
+            Value::parse(" \t\r\n\n").err().unwrap().kind(),
+            ParseErrorKind::ExpectValue
+        );
     }
 
     #[test]
     fn parse_invalid_value() {
-        assert_eq!(Value::parse("nul").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("?").err().unwrap(), ParseError::InvalidValue);
-
-        assert_eq!(Value::parse("+0").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("+1").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse(".123").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("1.").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("INF").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("inf").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("NAN").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("NaN").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse("nan").err().unwrap(), ParseError::InvalidValue);
-
-        assert_eq!(Value::parse("[1,]").err().unwrap(), ParseError::InvalidValue);
-        assert_eq!(Value::parse(r#"["a", nul]"#).err().unwrap(), ParseError::InvalidValue);
+        assert_eq!(Value::parse("nul").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("?").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+
+        assert_eq!(Value::parse("+0").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("+1").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse(".123").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("1.").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("INF").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("inf").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("NAN").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("NaN").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(Value::parse("nan").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+
+        assert_eq!(Value::parse("[1,]").err().unwrap().kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(
+            Value::parse(r#"["a", nul]"#).err().unwrap().kind(),
+            ParseErrorKind::InvalidValue
+        );
     }
 
     #[test]
     fn parse_root_not_singular() {
-        assert_eq!(Value::parse("null x").err().unwrap(), ParseError::RootNotSingular);
         assert_eq!(
-            Value::parse(" \t\r\nnull\ntrue").err().unwrap(),
-            ParseError::RootNotSingular
+            Value::parse("null x").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
+        );
+        assert_eq!(
+            Value::parse(" \t\r\nnull\ntrue").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
         );
         assert_eq!(
-            Value::parse("null\n\r \ttrue\r \t\r").err().unwrap(),
-            ParseError::RootNotSingular
+            Value::parse("null\n\r \ttrue\r \t\r").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
         );
 
-        assert_eq!(Value::parse("0123").err().unwrap(), ParseError::RootNotSingular);
-        assert_eq!(Value::parse("0x0").err().unwrap(), ParseError::RootNotSingular);
-        assert_eq!(Value::parse("0x123").err().unwrap(), ParseError::RootNotSingular);
+        assert_eq!(
+            Value::parse("0123").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
+        );
+        assert_eq!(
+            Value::parse("0x0").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
+        );
+        assert_eq!(
+            Value::parse("0x123").err().unwrap().kind(),
+            ParseErrorKind::RootNotSingular
+        );
     }
 
+    #[cfg(not(feature = "arbitrary_precision"))]
+    #[test]
+    fn parse_big_integer() {
+        let parse = |json: &str| match &Value::parse(json).unwrap() {
+            Value::Number(n) => n.clone(),
+            _ => panic!("expected a number"),
+        };
+        assert!(matches!(parse("9223372036854775807"), Number::Int(i64::MAX)));
+        assert!(matches!(parse("-9223372036854775808"), Number::Int(i64::MIN)));
+        assert!(matches!(
+            parse("9223372036854775808"),
+            Number::UInt(9223372036854775808)
+        ));
+        assert!(matches!(parse("18446744073709551615"), Number::UInt(u64::MAX)));
+        assert!(matches!(
+            parse("18446744073709551616"),
+            Number::UInt128(18446744073709551616)
+        ));
+        assert!(matches!(
+            parse("-9223372036854775809"),
+            Number::Int128(-9223372036854775809)
+        ));
+        assert!(matches!(
+            parse("340282366920938463463374607431768211455"),
+            Number::UInt128(u128::MAX)
+        ));
+        assert!(matches!(
+            parse("-170141183460469231731687303715884105728"),
+            Number::Int128(i128::MIN)
+        ));
+        // 超出128位的整数和带小数点或指数的数字仍然是浮点数
+        assert!(matches!(parse("340282366920938463463374607431768211456"), Number::Float(n) if n == 2f64.powi(128)));
+        assert!(matches!(
+            parse("-170141183460469231731687303715884105729"),
+            Number::Float(_)
+        ));
+        assert!(matches!(parse("18446744073709551615.0"), Number::Float(_)));
+        assert!(matches!(parse("1e2"), Number::Float(n) if n == 100.0));
+
+        for json in [
+            "18446744073709551615",
+            "-170141183460469231731687303715884105728",
+            "[9223372036854775808]",
+        ] {
+            assert_eq!(Value::parse(json).unwrap().to_string(), json);
+        }
+    }
+
+    // 启用arbitrary_precision时保留原始文本，不存在超出范围的数字
+    #[cfg(not(feature = "arbitrary_precision"))]
     #[test]
     fn parse_number_too_big() {
-        assert_eq!(Value::parse("1e309").err().unwrap(), ParseError::NumberTooBig);
-        assert_eq!(Value::parse("-1e309").err().unwrap(), ParseError::NumberTooBig);
+        assert_eq!(
+            Value::parse("1e309").err().unwrap().kind(),
+            ParseErrorKind::NumberTooBig
+        );
+        assert_eq!(
+            Value::parse("-1e309").err().unwrap().kind(),
+            ParseErrorKind::NumberTooBig
+        );
     }
 
     #[test]
     fn parse_miss_quotation_mark() {
-        assert_eq!(Value::parse(r#"""#).err().unwrap(), ParseError::MissQuotationMark);
-        assert_eq!(Value::parse(r#""abc"#).err().unwrap(), ParseError::MissQuotationMark);
+        assert_eq!(
+            Value::parse(r#"""#).err().unwrap().kind(),
+            ParseErrorKind::MissQuotationMark
+        );
+        assert_eq!(
+            Value::parse(r#""abc"#).err().unwrap().kind(),
+            ParseErrorKind::MissQuotationMark
+        );
     }
 
     #[test]
     fn parse_invalid_string_escape() {
-        assert_eq!(Value::parse(r#""\v""#).err().unwrap(), ParseError::InvalidStringEscape);
-        assert_eq!(Value::parse(r#""\'""#).err().unwrap(), ParseError::InvalidStringEscape);
-        assert_eq!(Value::parse(r#""\0""#).err().unwrap(), ParseError::InvalidStringEscape);
         assert_eq!(
-            Value::parse(r#""\x12""#).err().unwrap(),
-            ParseError::InvalidStringEscape
+            Value::parse(r#""\v""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidStringEscape
+        );
+        assert_eq!(
+            Value::parse(r#""\'""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidStringEscape
+        );
+        assert_eq!(
+            Value::parse(r#""\0""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidStringEscape
+        );
+        assert_eq!(
+            Value::parse(r#""\x12""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidStringEscape
         );
     }
 
     #[test]
     fn parse_invalid_string_char() {
-        assert_eq!(Value::parse("\"\x01\"").err().unwrap(), ParseError::InvalidStringChar);
-        assert_eq!(Value::parse("\"\x1F\"").err().unwrap(), ParseError::InvalidStringChar);
+        assert_eq!(
+            Value::parse("\"\x01\"").err().unwrap().kind(),
+            ParseErrorKind::InvalidStringChar
+        );
+        assert_eq!(
+            Value::parse("\"\x1F\"").err().unwrap().kind(),
+            ParseErrorKind::InvalidStringChar
+        );
+    }
+
+    #[test]
+    fn parse_invalid_utf8() {
+        let error = |json: &[u8]| {
+            let e = Value::parse_slice(json).err().unwrap();
+            (e.kind(), e.offset())
+        };
+        assert_eq!(error(b"\"\xff\""), (ParseErrorKind::InvalidUtf8, 1));
+        assert_eq!(error(b"[\"ab\xc3\""), (ParseErrorKind::InvalidUtf8, 4));
+        // 过长编码和代理项的UTF-8编码都不合法
+        assert_eq!(error(b"\"\\n\xc0\x80\""), (ParseErrorKind::InvalidUtf8, 3));
+        assert_eq!(error(b"\"\xed\xa0\x80\""), (ParseErrorKind::InvalidUtf8, 1));
+        assert_eq!(error(b"{\"\xe4\xbd\":1}"), (ParseErrorKind::MissKey, 1));
+        // 字符串以外的非ASCII字节不是合法的值
+        assert_eq!(error(b"\xef\xbb\xbf{}"), (ParseErrorKind::InvalidValue, 0));
+        assert_eq!(
+            Value::parse_slice(b"\"\xe4\xbd\xa0\"").unwrap(),
+            Value::String("你".to_string())
+        );
+
+        // 以前会panic的输入
+        assert_eq!(error(b"["), (ParseErrorKind::ExpectValue, 1));
+        assert_eq!(error(b"{"), (ParseErrorKind::MissKey, 1));
+        assert_eq!(error(b"-"), (ParseErrorKind::InvalidValue, 1));
+        assert_eq!(error(b"\"\\"), (ParseErrorKind::MissQuotationMark, 1));
     }
 
     #[test]
     fn parse_invalid_unicode_hex() {
-        assert_eq!(Value::parse(r#""\u""#).err().unwrap(), ParseError::InvalidUnicodeHex);
-        assert_eq!(Value::parse(r#""\u0""#).err().unwrap(), ParseError::InvalidUnicodeHex);
-        assert_eq!(Value::parse(r#""\u01""#).err().unwrap(), ParseError::InvalidUnicodeHex);
-        assert_eq!(Value::parse(r#""\u012""#).err().unwrap(), ParseError::InvalidUnicodeHex);
         assert_eq!(
-            Value::parse(r#""\u/000""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
+        );
+        assert_eq!(
+            Value::parse(r#""\u0""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
+        );
+        assert_eq!(
+            Value::parse(r#""\u01""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\uG000""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u012""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u0/00""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u/000""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u0G00""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\uG000""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u00/0""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u0/00""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u00G0""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u0G00""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u000/""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u00/0""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u000G""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u00G0""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
         assert_eq!(
-            Value::parse(r#""\u 123""#).err().unwrap(),
-            ParseError::InvalidUnicodeHex
+            Value::parse(r#""\u000/""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
+        );
+        assert_eq!(
+            Value::parse(r#""\u000G""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
+        );
+        assert_eq!(
+            Value::parse(r#""\u 123""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeHex
         );
     }
 
     #[test]
     fn parse_invalid_unicode_surrogate() {
         assert_eq!(
-            Value::parse(r#""\uD800""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uD800""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
         assert_eq!(
-            Value::parse(r#""\uDBFF""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uDBFF""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
         assert_eq!(
-            Value::parse(r#""\uD800\\""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uD800\\""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
         assert_eq!(
-            Value::parse(r#""\uD800\uDBFF""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uD800\uDBFF""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
         assert_eq!(
-            Value::parse(r#""\uD800""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uD800""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
         assert_eq!(
-            Value::parse(r#""\uD800\uE000""#).err().unwrap(),
-            ParseError::InvalidUnicodeSurrogate
+            Value::parse(r#""\uD800\uE000""#).err().unwrap().kind(),
+            ParseErrorKind::InvalidUnicodeSurrogate
         );
     }
 
     #[test]
     fn parse_miss_comma_or_square_bracket() {
-        assert_eq!(Value::parse("[1").err().unwrap(), ParseError::MissCommaOrSquareBracket);
-        assert_eq!(Value::parse("[1}").err().unwrap(), ParseError::MissCommaOrSquareBracket);
         assert_eq!(
-            Value::parse("[1 2").err().unwrap(),
-            ParseError::MissCommaOrSquareBracket
+            Value::parse("[1").err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrSquareBracket
+        );
+        assert_eq!(
+            Value::parse("[1}").err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrSquareBracket
+        );
+        assert_eq!(
+            Value::parse("[1 2").err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrSquareBracket
+        );
+        assert_eq!(
+            Value::parse("[[]").err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrSquareBracket
         );
-        assert_eq!(Value::parse("[[]").err().unwrap(), ParseError::MissCommaOrSquareBracket);
     }
 
     #[test]
     fn parse_miss_key() {
-        assert_eq!(Value::parse("{:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{1:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{true:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{false:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{null:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{[]:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse("{{}:1,").err().unwrap(), ParseError::MissKey);
-        assert_eq!(Value::parse(r#"{"a":1,"#).err().unwrap(), ParseError::MissKey);
+        assert_eq!(Value::parse("{:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{1:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{true:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{false:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{null:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{[]:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(Value::parse("{{}:1,").err().unwrap().kind(), ParseErrorKind::MissKey);
+        assert_eq!(
+            Value::parse(r#"{"a":1,"#).err().unwrap().kind(),
+            ParseErrorKind::MissKey
+        );
     }
 
     #[test]
     fn parse_miss_colon() {
-        assert_eq!(Value::parse(r#"{"a""#).err().unwrap(), ParseError::MissColon);
-        assert_eq!(Value::parse(r#"{"a","b"}"#).err().unwrap(), ParseError::MissColon);
+        assert_eq!(Value::parse(r#"{"a""#).err().unwrap().kind(), ParseErrorKind::MissColon);
+        assert_eq!(
+            Value::parse(r#"{"a","b"}"#).err().unwrap().kind(),
+            ParseErrorKind::MissColon
+        );
     }
 
     #[test]
     fn parse_miss_comma_or_curly_bracket() {
         assert_eq!(
-            Value::parse(r#"{"a":1"#).err().unwrap(),
-            ParseError::MissCommaOrCurlyBracket
+            Value::parse(r#"{"a":1"#).err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrCurlyBracket
+        );
+        assert_eq!(
+            Value::parse(r#"{"a":1]"#).err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrCurlyBracket
+        );
+        assert_eq!(
+            Value::parse(r#"{"a":1 "b"}"#).err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrCurlyBracket
+        );
+        assert_eq!(
+            Value::parse(r#"{"a":{}"#).err().unwrap().kind(),
+            ParseErrorKind::MissCommaOrCurlyBracket
+        );
+    }
+
+    #[test]
+    fn parse_depth_limit_exceeded() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Value::parse(&nested(128)).is_ok());
+        let e = Value::parse(&nested(129)).err().unwrap();
+        assert_eq!(
+            (e.kind(), e.offset(), e.path().len()),
+            (ParseErrorKind::DepthLimitExceeded, 128, 128)
+        );
+
+        // 大量未闭合的括号不会导致栈溢出
+        let e = Value::parse(&"[".repeat(100_000)).err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::DepthLimitExceeded);
+
+        let options = ParseOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(Value::parse_with_options(r#"{"a": [1]}"#, options.clone()).is_ok());
+        let e = Value::parse_with_options(r#"{"a": [{"b": 1}]}"#, options.clone())
+            .err()
+            .unwrap();
+        assert_eq!(
+            (e.kind(), e.path_string()),
+            (ParseErrorKind::DepthLimitExceeded, "$.a[0]".to_string())
+        );
+        let e = Value::from_reader_with_options("[[[]]]".as_bytes(), options)
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::DepthLimitExceeded);
+    }
+
+    #[test]
+    fn parse_limits() {
+        let limits = ParseOptions {
+            max_document_size: 64,
+            max_string_length: 4,
+            max_array_length: 3,
+            max_object_length: 2,
+            max_nodes: 8,
+            ..Default::default()
+        };
+        let error = |json: &str| {
+            let e = Value::parse_with_options(json, limits.clone()).err().unwrap();
+            let from_reader = Value::from_reader_with_options(json.as_bytes(), limits.clone());
+            assert_eq!(from_reader.err().unwrap(), e);
+            (e.kind(), e.offset(), e.path_string())
+        };
+        assert!(Value::parse_with_options(r#"[1, "abcd", {"a": [], "b": null}]"#, limits.clone()).is_ok());
+
+        let document = format!("[{}1]", " ".repeat(64));
+        assert_eq!(
+            error(&document),
+            (ParseErrorKind::DocumentTooLarge, 64, "$".to_string())
+        );
+        assert_eq!(
+            error(r#"["abcde"]"#),
+            (ParseErrorKind::StringTooLong, 1, "$[0]".to_string())
+        );
+        assert_eq!(
+            error(r#"{"abcde": 1}"#),
+            (ParseErrorKind::StringTooLong, 1, "$".to_string())
         );
+        // 按转义后的长度计算
+        assert!(Value::parse_with_options(r#""\u4f60\n""#, limits.clone()).is_ok());
+        assert_eq!(
+            error(r#"[1, 2, 3, 4]"#),
+            (ParseErrorKind::ArrayTooLong, 10, "$[3]".to_string())
+        );
+        assert_eq!(
+            error(r#"{"a": 1, "b": 2, "c": 3}"#),
+            (ParseErrorKind::ObjectTooLong, 17, "$".to_string())
+        );
+        assert_eq!(
+            error(r#"[[1, 2], [3, 4], [5]]"#),
+            (ParseErrorKind::TooManyNodes, 18, "$[2][0]".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_duplicate_keys() {
+        let json = r#"{"a": [1], "b": {"c": 1, "c": 2}, "a": 2, "a": {}}"#;
+        let parse = |policy: DuplicateKeys| {
+            let options = ParseOptions {
+                duplicate_keys: policy,
+                ..Default::default()
+            };
+            Value::parse_with_options(json, options)
+        };
         assert_eq!(
-            Value::parse(r#"{"a":1]"#).err().unwrap(),
-            ParseError::MissCommaOrCurlyBracket
+            parse(DuplicateKeys::LastWins).unwrap(),
+            Value::parse(r#"{"a": {}, "b": {"c": 2}}"#).unwrap()
         );
+        assert_eq!(parse(DuplicateKeys::LastWins).unwrap(), Value::parse(json).unwrap());
         assert_eq!(
-            Value::parse(r#"{"a":1 "b"}"#).err().unwrap(),
-            ParseError::MissCommaOrCurlyBracket
+            parse(DuplicateKeys::FirstWins).unwrap(),
+            Value::parse(r#"{"a": [1], "b": {"c": 1}}"#).unwrap()
         );
+        // 第一个值本身是数组时也不会与收集到的值混在一起
         assert_eq!(
-            Value::parse(r#"{"a":{}"#).err().unwrap(),
-            ParseError::MissCommaOrCurlyBracket
+            parse(DuplicateKeys::CollectAll).unwrap(),
+            Value::parse(r#"{"a": [[1], 2, {}], "b": {"c": [1, 2]}}"#).unwrap()
         );
+
+        let e = parse(DuplicateKeys::Error).err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::DuplicateKey);
+        assert_eq!((e.offset(), e.column()), (25, 26));
+        assert_eq!(e.path_string(), "$.b");
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..Default::default()
+        };
+        assert!(Value::parse_with_options(r#"[{"a": 1}, {"a": 2}]"#, options).is_ok());
+    }
+
+    #[test]
+    fn deep_nesting_without_recursion() {
+        let depth = 100_000;
+        let json = "[{\"a\":".repeat(depth) + "null" + &"}]".repeat(depth);
+        let unlimited = || ParseOptions {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        let v = Value::parse_with_options(&json, unlimited()).unwrap();
+        assert_eq!(v.to_string(), json);
+        let cloned = v.clone();
+        assert_eq!(cloned, v);
+        let other = Value::parse_with_options(&json.replace("null", "true"), unlimited()).unwrap();
+        assert_ne!(other, v);
+        for v in [v, cloned, other] {
+            v.drop_deep();
+        }
+        // 解析出错时释放已构建的部分同样不会栈溢出
+        let e = Value::parse_with_options(&json[..json.len() - 1], unlimited())
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::MissCommaOrSquareBracket);
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let e = Value::parse("{\n  \"a\": [1, 2,\n    tru]\n}").err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::InvalidValue);
+        assert_eq!(e.offset(), 20);
+        assert_eq!((e.line(), e.column()), (3, 5));
+        assert_eq!(
+            e.path(),
+            &[PathSegment::Key("a".to_string()), PathSegment::Index(2)][..]
+        );
+        assert_eq!(e.path_string(), "$.a[2]");
+        assert_eq!(e.to_string(), "invalid value at line 3 column 5 (byte 20), path $.a[2]");
+
+        let e = Value::parse(r#"{"名字" "x"}"#).err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::MissColon);
+        assert_eq!((e.offset(), e.line(), e.column()), (10, 1, 7));
+        assert!(e.path().is_empty());
+
+        let e = Value::parse("[1 2").err().unwrap();
+        assert_eq!((e.offset(), e.column()), (3, 4));
+
+        let e = Value::parse(r#"["ok", "\x"]"#).err().unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::InvalidStringEscape);
+        assert_eq!((e.offset(), e.path_string().as_str()), (8, "$[1]"));
+
+        let boxed: Box<dyn std::error::Error> = Box::new(Value::parse("null x").err().unwrap());
+        assert_eq!(
+            boxed.to_string(),
+            "unexpected characters after the root value at line 1 column 6 (byte 5), path $"
+        );
+    }
+
+    // 每次只返回一个字节，用来覆盖记号跨越缓冲区边界的情况
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl std::io::Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_from_reader() {
+        for json in [
+            "null",
+            " true ",
+            "-1.234E+10",
+            r#""\uD834\uDD1E \" \\ \n""#,
+            r#"[ null , false , true , 123 , "abc", [0, 1] ]"#,
+            r#"{ "n" : null , "a" : [ 1, 2, 3 ], "o" : { "1" : 1, "2" : 2 } }"#,
+        ] {
+            assert_eq!(
+                Value::from_reader(OneByteReader(json.as_bytes())).unwrap(),
+                Value::parse(json).unwrap()
+            );
+            assert_eq!(
+                Value::from_reader(json.as_bytes()).unwrap(),
+                Value::parse(json).unwrap()
+            );
+        }
+
+        for json in ["[1, 2", r#"{"a" 1}"#, "nul", "1 2", r#""abc"#, ""] {
+            assert_eq!(
+                Value::from_reader(OneByteReader(json.as_bytes())).err(),
+                Value::parse(json).err()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_from_reader_large_input() {
+        let mut json = String::from("[\n");
+        for i in 0..5000 {
+            json.push_str(&format!("  {{\"id\": {i}, \"name\": \"item {i}\"}},\n"));
+        }
+        json.push_str("  tru\n]");
+        let e = Value::from_reader(json.as_bytes()).err().unwrap();
+        assert_eq!(e, Value::parse(&json).err().unwrap());
+        assert_eq!((e.line(), e.column()), (5002, 3));
+        assert_eq!(e.path_string(), "$[5000]");
+
+        let json = json.replace("tru", "true");
+        assert_eq!(
+            Value::from_reader(OneByteReader(json.as_bytes())).unwrap()[4999]["name"],
+            Value::String("item 4999".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_from_reader_io_error() {
+        struct FailingReader;
+        impl std::io::Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("broken pipe"))
+            }
+        }
+        let e = Value::from_reader(std::io::Read::chain("[1, ".as_bytes(), FailingReader))
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), ParseErrorKind::Io(std::io::ErrorKind::Other));
+        assert_eq!(e.offset(), 4);
     }
 
     fn test_roundtrip(json: &str) {
@@ -977,8 +1475,7 @@ mod tests {
         match Value::parse(&v1.to_string()) {
             Ok(v2) => assert_eq!(v1, v2),
             Err(e) => {
-                println!("json:\n{}", json);
-                Err(e).unwrap()
+                panic!("json:\n{}\n{}", json, e);
             }
         }
     }
@@ -1014,6 +1511,34 @@ mod tests {
         test_roundtrip("-1.7976931348623157e+308");
     }
 
+    #[test]
+    fn stringify_float_options() {
+        let v = Value::Array(vec![
+            Value::Number(Number::Float(1.0)),
+            Value::Number(Number::Int(1)),
+            Value::Number(Number::Float(f64::NAN)),
+            Value::Number(Number::Float(f64::INFINITY)),
+        ]);
+        assert_eq!(v.to_string(), "[1,1,null,null]");
+        let options = WriteOptions {
+            non_finite_floats: NonFiniteFloats::String,
+            float_suffix: true,
+        };
+        assert_eq!(
+            v.to_string_with_options(options).unwrap(),
+            r#"[1.0,1,"NaN","Infinity"]"#
+        );
+        let options = WriteOptions {
+            non_finite_floats: NonFiniteFloats::Error,
+            ..Default::default()
+        };
+        assert_eq!(
+            v.to_string_with_options(options.clone()),
+            Err(WriteError::NonFiniteFloat)
+        );
+        assert_eq!(v[0].to_string_with_options(options).unwrap(), "1");
+    }
+
     #[test]
     fn stringify_string() {
         test_roundtrip(r#""""#);
@@ -1021,6 +1546,9 @@ mod tests {
         test_roundtrip(r#""Hello\nWorld""#);
         test_roundtrip(r#""\" \\ / \b \f \n \r \t""#);
         test_roundtrip(r#""Hello\u0000World""#);
+        // 只有退格和换页写成\b、\f，字母b、f原样输出
+        test_roundtrip(r#""buffer""#);
+        assert_eq!(Value::String("\x08\x0C".to_string()).to_string(), r#""\b\f""#);
     }
 
     #[test]
@@ -1037,6 +1565,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stringify_object_order() {
+        let json = r#"{"z":1,"a":{"y":2,"c":3},"m":[]}"#;
+        let v = Value::parse(json).unwrap();
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(v.to_string(), json);
+        #[cfg(not(feature = "preserve_order"))]
+        assert_eq!(v.to_string(), r#"{"a":{"c":3,"y":2},"m":[],"z":1}"#);
+        // 比较与成员顺序无关
+        assert_eq!(v, Value::parse(r#"{"m":[],"a":{"c":3,"y":2},"z":1}"#).unwrap());
+        assert_ne!(v, Value::parse(r#"{"m":[],"a":{"c":3,"x":2},"z":1}"#).unwrap());
+    }
+
     #[test]
     fn access_array() {
         let mut v = Value::parse(r#"[ null , false , true , 123 , "abc" ]"#).ok().unwrap();